@@ -253,9 +253,25 @@ impl Store {
 			);
 
 			for chunk in chunks.into_iter() {
-				if !have_chunks.contains(&chunk.index) {
-					have_chunks.push(chunk.index);
+				// chunks we already have are ignored, so that a chunk sent twice is
+				// neither stored twice nor counted twice towards reconstruction.
+				if have_chunks.contains(&chunk.index) {
+					continue;
 				}
+
+				// a chunk is only recorded once it is known to match the erasure root,
+				// so that a bad chunk can't take the place of the valid one for its index.
+				if !erasure::verify_chunk_against_root(&chunk, &receipt.commitments.erasure_root) {
+					warn!(
+						target: LOG_TARGET,
+						"Discarding erasure chunk {} of candidate {} not matching its erasure root",
+						chunk.index,
+						candidate_hash,
+					);
+					continue;
+				}
+
+				have_chunks.push(chunk.index);
 				v.push(chunk);
 			}
 
@@ -273,12 +289,26 @@ impl Store {
 			// If there are no block data in the store at this point,
 			// check that they can be reconstructed now and add them to store if they can.
 			if self.execution_data(&candidate_hash).is_none() {
-				if let Ok(available_data) = erasure::reconstruct(
+				let erasure_root = &receipt.commitments.erasure_root;
+
+				// every stored chunk was checked against the erasure root when it was
+				// imported, so only the root of the reconstructed data is checked here.
+				let reconstructed = erasure::reconstruct_with_policy(
 					n_validators as usize,
-					v.iter().map(|chunk| (chunk.chunk.as_ref(), chunk.index as usize)),
-				)
-				{
-					self.make_available(*candidate_hash, available_data)?;
+					erasure_root,
+					&v,
+					erasure::ProofPolicy::TrustRoot,
+				);
+
+				match reconstructed {
+					Ok(available_data) => self.make_available(*candidate_hash, available_data)?,
+					Err(erasure::Error::NotEnoughChunks) => {}
+					Err(e) => warn!(
+						target: LOG_TARGET,
+						"Failed to reconstruct data for candidate {}: {}",
+						candidate_hash,
+						e,
+					),
 				}
 			}
 
@@ -421,32 +451,26 @@ mod tests {
 		let mut candidate_1 = AbridgedCandidateReceipt::default();
 		let mut candidate_2 = AbridgedCandidateReceipt::default();
 
+		let available_data_1 = available_data(&[1, 2, 3]);
+		let available_data_2 = available_data(&[4, 5, 6]);
+
+		let (chunks_1, erasure_root_1) = erasure::available_data_to_chunks(3, &available_data_1).unwrap();
+		let (chunks_2, erasure_root_2) = erasure::available_data_to_chunks(3, &available_data_2).unwrap();
+
 		candidate_1.parachain_index = para_id_1;
-		candidate_1.commitments.erasure_root = [6; 32].into();
+		candidate_1.commitments.erasure_root = erasure_root_1;
 		candidate_1.relay_parent = relay_parent;
 
 		candidate_2.parachain_index = para_id_2;
-		candidate_2.commitments.erasure_root = [6; 32].into();
+		candidate_2.commitments.erasure_root = erasure_root_2;
 		candidate_2.relay_parent = relay_parent;
 
 
 		let candidate_1_hash = candidate_1.hash();
 		let candidate_2_hash = candidate_2.hash();
 
-		let available_data_1 = available_data(&[1, 2, 3]);
-		let available_data_2 = available_data(&[4, 5, 6]);
-
-		let erasure_chunk_1 = ErasureChunk {
-			chunk: vec![10, 20, 30],
-			index: 1,
-			proof: vec![],
-		};
-
-		let erasure_chunk_2 = ErasureChunk {
-			chunk: vec![40, 50, 60],
-			index: 1,
-			proof: vec![],
-		};
+		let erasure_chunk_1 = chunks_1[1].clone();
+		let erasure_chunk_2 = chunks_2[1].clone();
 
 		let store = Store::new_in_memory();
 		store.make_available(candidate_1_hash, available_data_1.clone()).unwrap();
@@ -498,7 +522,7 @@ mod tests {
 
 		let mut candidate = AbridgedCandidateReceipt::default();
 		candidate.parachain_index = para_id;
-		candidate.commitments.erasure_root = [6; 32].into();
+		candidate.commitments.erasure_root = branches.root();
 		candidate.relay_parent = relay_parent;
 
 		let candidate_hash = candidate.hash();
@@ -526,6 +550,111 @@ mod tests {
 		assert_eq!(store.execution_data(&candidate_hash), Some(execution_data(&available_data)));
 	}
 
+	#[test]
+	fn duplicate_chunks_are_stored_once() {
+		let available_data = available_data(&[42; 8]);
+		let n_validators = 5;
+
+		let (chunks, erasure_root) = erasure::available_data_to_chunks(
+			n_validators,
+			&available_data,
+		).unwrap();
+
+		let mut candidate = AbridgedCandidateReceipt::default();
+		candidate.commitments.erasure_root = erasure_root;
+
+		let candidate_hash = candidate.hash();
+
+		let store = Store::new_in_memory();
+
+		store.add_candidate(&candidate).unwrap();
+		store.add_erasure_chunks(
+			n_validators as u32,
+			&candidate_hash,
+			vec![chunks[0].clone(), chunks[0].clone()],
+		).unwrap();
+		store.add_erasure_chunks(n_validators as u32, &candidate_hash, vec![chunks[0].clone()]).unwrap();
+
+		let stored: Vec<ErasureChunk> = store.query_inner(
+			columns::DATA,
+			&erasure_chunks_key(&candidate_hash),
+		).unwrap();
+		assert_eq!(stored, vec![chunks[0].clone()]);
+		assert!(store.execution_data(&candidate_hash).is_none());
+
+		store.add_erasure_chunks(n_validators as u32, &candidate_hash, vec![chunks[1].clone()]).unwrap();
+		assert_eq!(store.execution_data(&candidate_hash), Some(execution_data(&available_data)));
+	}
+
+	#[test]
+	fn bad_chunk_does_not_shadow_valid_one() {
+		let available_data = available_data(&[42; 8]);
+		let n_validators = 5;
+
+		let (chunks, erasure_root) = erasure::available_data_to_chunks(
+			n_validators,
+			&available_data,
+		).unwrap();
+
+		let mut candidate = AbridgedCandidateReceipt::default();
+		candidate.commitments.erasure_root = erasure_root;
+
+		let candidate_hash = candidate.hash();
+
+		let mut bad_chunk = chunks[0].clone();
+		bad_chunk.chunk[0] ^= 1;
+
+		let store = Store::new_in_memory();
+
+		store.add_candidate(&candidate).unwrap();
+		store.add_erasure_chunks(n_validators as u32, &candidate_hash, vec![bad_chunk]).unwrap();
+		assert!(store.get_erasure_chunk(&candidate_hash, 0).is_none());
+
+		store.add_erasure_chunks(n_validators as u32, &candidate_hash, vec![chunks[0].clone()]).unwrap();
+		assert_eq!(store.get_erasure_chunk(&candidate_hash, 0), Some(chunks[0].clone()));
+
+		store.add_erasure_chunks(n_validators as u32, &candidate_hash, vec![chunks[1].clone()]).unwrap();
+		assert_eq!(store.execution_data(&candidate_hash), Some(execution_data(&available_data)));
+	}
+
+	#[test]
+	fn reconstruction_with_wrong_erasure_root_is_not_stored() {
+		let available_data = available_data(&[42; 8]);
+		let n_validators = 5;
+
+		// tamper with a systematic chunk and commit to the tampered chunks, so that
+		// every proof verifies but the data decoded from them encodes to another root.
+		let mut erasure_chunks = erasure::obtain_chunks(
+			n_validators,
+			&available_data,
+		).unwrap();
+		let last = erasure_chunks[0].len() - 1;
+		erasure_chunks[0][last] ^= 1;
+
+		let branches = erasure::branches(erasure_chunks.as_ref());
+
+		let mut candidate = AbridgedCandidateReceipt::default();
+		candidate.commitments.erasure_root = branches.root();
+
+		let candidate_hash = candidate.hash();
+
+		let chunks: Vec<_> = branches
+			.enumerate()
+			.map(|(index, (proof, chunk))| ErasureChunk {
+				chunk: chunk.to_vec(),
+				proof,
+				index: index as u32,
+			})
+			.collect();
+
+		let store = Store::new_in_memory();
+
+		store.add_candidate(&candidate).unwrap();
+		store.add_erasure_chunks(n_validators as u32, &candidate_hash, chunks).unwrap();
+
+		assert!(store.execution_data(&candidate_hash).is_none());
+	}
+
 	#[test]
 	fn add_validator_index_works() {
 		let relay_parent = [42; 32].into();
@@ -552,7 +681,10 @@ mod tests {
 		let validator_index = 3;
 		let n_validators = 10;
 		let relay_parent = [42; 32].into();
-		let erasure_root_1 = [11; 32].into();
+		let (chunks, erasure_root_1) = erasure::available_data_to_chunks(
+			n_validators as usize,
+			&available_data(&[1, 2, 3]),
+		).unwrap();
 		let erasure_root_2 = [12; 32].into();
 		let mut receipt_1 = AbridgedCandidateReceipt::default();
 		let mut receipt_2 = AbridgedCandidateReceipt::default();
@@ -569,11 +701,7 @@ mod tests {
 		let receipt_1_hash = receipt_1.hash();
 		let receipt_2_hash = receipt_2.hash();
 
-		let chunk = ErasureChunk {
-			chunk: vec![1, 2, 3],
-			index: validator_index,
-			proof: Vec::new(),
-		};
+		let chunk = chunks[validator_index as usize].clone();
 		let candidates = vec![receipt_1_hash, receipt_2_hash];
 
 		let store = Store::new_in_memory();
@@ -696,6 +696,20 @@ mod tests {
 	use tokio::runtime::Runtime;
 	use parking_lot::Mutex;
 	use crate::store::AwaitedFrontierEntry;
+	use polkadot_erasure_coding as erasure;
+	use polkadot_primitives::parachain::{BlockData, PoVBlock};
+
+	// erasure chunks of some available data, along with their erasure root.
+	fn test_chunks(n_validators: u32) -> (Vec<ErasureChunk>, Hash) {
+		let available_data = AvailableData {
+			pov_block: PoVBlock {
+				block_data: BlockData(vec![1, 2, 3]),
+			},
+			omitted_validation: Default::default(),
+		};
+
+		erasure::available_data_to_chunks(n_validators as usize, &available_data).unwrap()
+	}
 
 	#[derive(Default, Clone)]
 	struct TestErasureNetwork {
@@ -753,9 +767,12 @@ mod tests {
 
 		let store = Store::new_in_memory();
 
+		let (chunks, erasure_root) = test_chunks(n_validators);
+
 		let mut candidate = AbridgedCandidateReceipt::default();
 
 		candidate.relay_parent = relay_parent;
+		candidate.commitments.erasure_root = erasure_root;
 		let candidate_hash = candidate.hash();
 
 		// Tell the store our validator's position and the number of validators at given point.
@@ -797,11 +814,7 @@ mod tests {
 		);
 
 		// Complete the chunk request.
-		chunk_sender.send(ErasureChunk {
-			chunk: vec![1, 2, 3],
-			index: local_id as u32,
-			proof: vec![],
-		}).unwrap();
+		chunk_sender.send(chunks[local_id as usize].clone()).unwrap();
 
 		// wait until worker thread has de-registered the listener for a
 		// particular chunk.
@@ -832,12 +845,13 @@ mod tests {
 		let mut runtime = Runtime::new().unwrap();
 		let relay_parent = [1; 32].into();
 		let erasure_root_1 = [2; 32].into();
-		let erasure_root_2 = [3; 32].into();
 		let pov_block_hash_1 = [4; 32].into();
 		let pov_block_hash_2 = [5; 32].into();
 		let local_id = 2;
 		let n_validators = 4;
 
+		let (chunks_2, erasure_root_2) = test_chunks(n_validators);
+
 		let mut candidate_1 = AbridgedCandidateReceipt::default();
 		candidate_1.commitments.erasure_root = erasure_root_1;
 		candidate_1.pov_block_hash = pov_block_hash_1;
@@ -863,11 +877,7 @@ mod tests {
 		store.add_erasure_chunks(
 			n_validators,
 			&candidate_2_hash,
-			vec![ErasureChunk {
-				chunk: vec![1, 2, 3],
-				index: local_id,
-				proof: Vec::default(),
-			}],
+			vec![chunks_2[local_id as usize].clone()],
 		).unwrap();
 
 		let network = TestErasureNetwork::default();
@@ -27,7 +27,7 @@
 use codec::{Encode, Decode};
 use reed_solomon::galois_16::{self, ReedSolomon};
 use primitives::{Hash as H256, BlakeTwo256, HashT};
-use primitives::parachain::AvailableData;
+use primitives::parachain::{AvailableData, ErasureChunk, ValidatorIndex};
 use sp_core::Blake2Hasher;
 use trie::{EMPTY_PREFIX, MemoryDB, Trie, TrieMut, trie_types::{TrieDBMut, TrieDB}};
 
@@ -35,8 +35,10 @@ use self::wrapped_shard::WrappedShard;
 
 mod wrapped_shard;
 
-// we are limited to the field order of GF(2^16), which is 65536
-const MAX_VALIDATORS: usize = <galois_16::Field as reed_solomon::Field>::ORDER;
+/// The maximum number of validators data can be erasure-coded for.
+///
+/// We are limited to the field order of GF(2^16), which is 65536.
+pub const MAX_VALIDATORS: usize = <galois_16::Field as reed_solomon::Field>::ORDER;
 
 /// Errors in erasure coding.
 #[derive(Debug, Clone, PartialEq, derive_more::Display)]
@@ -64,6 +66,8 @@ pub enum Error {
 	InvalidBranchProof,
 	/// Branch out of bounds.
 	BranchOutOfBounds,
+	/// The reconstructed data doesn't encode to the expected erasure root.
+	UnexpectedErasureRoot,
 }
 
 impl std::error::Error for Error { }
@@ -124,6 +128,30 @@ fn code_params(n_validators: usize) -> Result<CodeParams, Error> {
 	})
 }
 
+/// The parameters of the code used for `n_validators` validators, as the number of
+/// data shards and the total number of shards, i.e. one per validator.
+///
+/// The data shards are the systematic chunks, and the number of them is the
+/// `recovery_threshold`: any that many chunks suffice to reconstruct the data.
+/// The remaining shards hold parity, giving a coding rate of roughly 1/3.
+///
+/// Works only up to 65536 validators, and `n_validators` must be non-zero.
+pub fn coding_parameters(n_validators: usize) -> Result<(usize, usize), Error> {
+	let params = code_params(n_validators)?;
+	Ok((params.data_shards, params.data_shards + params.parity_shards))
+}
+
+/// The number of distinct chunks needed to reconstruct data erasure-coded for
+/// `n_validators` validators.
+///
+/// Data is encoded at a rate of roughly 1/3, tolerating up to `(n_validators - 1) / 3`
+/// faulty validators, so any `(n_validators - 1) / 3 + 1` chunks are sufficient.
+///
+/// Works only up to 65536 validators, and `n_validators` must be non-zero.
+pub fn recovery_threshold(n_validators: usize) -> Result<usize, Error> {
+	code_params(n_validators).map(|params| params.data_shards)
+}
+
 /// Obtain erasure-coded chunks, one for each validator.
 ///
 /// Works only up to 65536 validators, and `n_validators` must be non-zero.
@@ -148,8 +176,8 @@ pub fn obtain_chunks(n_validators: usize, available_data: &AvailableData)
 /// Reconstruct the block data from a set of chunks.
 ///
 /// Provide an iterator containing chunk data and the corresponding index.
-/// The indices of the present chunks must be indicated. If too few chunks
-/// are provided, recovery is not possible.
+/// The indices of the present chunks must be indicated, but the chunks may be
+/// provided in any order. If too few chunks are provided, recovery is not possible.
 ///
 /// Works only up to 65536 validators, and `n_validators` must be non-zero.
 pub fn reconstruct<'a, I: 'a>(n_validators: usize, chunks: I)
@@ -200,6 +228,152 @@ pub fn reconstruct<'a, I: 'a>(n_validators: usize, chunks: I)
 	}).or_else(|_| Err(Error::BadPayload))
 }
 
+/// Reconstruct the block data from the systematic chunks alone.
+///
+/// The first `f + 1` chunks hold the encoded data verbatim, so when all of
+/// them are present the data can be recovered by concatenating them, skipping
+/// the Reed-Solomon decode. Other chunks provided are ignored. If any
+/// systematic chunk is missing, `Error::NotEnoughChunks` is returned and
+/// callers should fall back to `reconstruct`.
+///
+/// Works only up to 65536 validators, and `n_validators` must be non-zero.
+pub fn reconstruct_from_systematic<'a, I: 'a>(n_validators: usize, chunks: I)
+	-> Result<AvailableData, Error>
+	where I: IntoIterator<Item=(&'a [u8], usize)>
+{
+	let params = code_params(n_validators)?;
+	let mut shards: Vec<Option<&'a [u8]>> = vec![None; params.data_shards];
+	let mut shard_len = None;
+	for (chunk_data, chunk_idx) in chunks.into_iter() {
+		if chunk_idx >= n_validators {
+			return Err(Error::ChunkIndexOutOfBounds(chunk_idx, n_validators));
+		}
+
+		if chunk_idx >= params.data_shards {
+			continue;
+		}
+
+		let shard_len = shard_len.get_or_insert_with(|| chunk_data.len());
+
+		if *shard_len % 2 != 0 {
+			return Err(Error::UnevenLength);
+		}
+
+		if *shard_len != chunk_data.len() || *shard_len == 0 {
+			return Err(Error::NonUniformChunks);
+		}
+
+		shards[chunk_idx] = Some(chunk_data);
+	}
+
+	let shards = shards.into_iter()
+		.collect::<Option<Vec<_>>>()
+		.ok_or(Error::NotEnoughChunks)?;
+
+	Decode::decode(&mut ShardInput {
+		remaining_len: shard_len.map(|s| s * params.data_shards).unwrap_or(0),
+		cur_shard: None,
+		shards: shards.into_iter(),
+	}).or_else(|_| Err(Error::BadPayload))
+}
+
+/// Check the merkle proof of a chunk against `erasure_root`.
+pub fn verify_chunk_against_root(chunk: &ErasureChunk, erasure_root: &H256) -> bool {
+	let expected = branch_hash(erasure_root, &chunk.proof, chunk.index as usize);
+	expected == Ok(BlakeTwo256::hash(&chunk.chunk))
+}
+
+/// Check the merkle proof of every chunk against `erasure_root`.
+///
+/// On failure, the indices of all chunks whose proofs don't match are returned,
+/// in the order the chunks were given.
+pub fn verify_chunks_against_root(chunks: &[ErasureChunk], erasure_root: &H256)
+	-> Result<(), Vec<ValidatorIndex>>
+{
+	let failed: Vec<_> = chunks.iter()
+		.filter(|chunk| !verify_chunk_against_root(chunk, erasure_root))
+		.map(|chunk| chunk.index)
+		.collect();
+
+	if failed.is_empty() {
+		Ok(())
+	} else {
+		Err(failed)
+	}
+}
+
+/// How thoroughly `reconstruct_with_policy` checks chunks against the erasure root.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProofPolicy {
+	/// Check the merkle proof of every chunk before decoding, ignoring those which
+	/// don't match, as well as the root of the reconstructed data.
+	Strict,
+	/// Skip the per-chunk merkle proofs and only check the root of the reconstructed
+	/// data. Corrupt chunks are still detected, but not which of them were corrupt,
+	/// and a single corrupt chunk makes the reconstruction fail.
+	TrustRoot,
+}
+
+impl Default for ProofPolicy {
+	fn default() -> Self {
+		ProofPolicy::Strict
+	}
+}
+
+/// Reconstruct the block data from erasure chunks, checking it against the erasure root
+/// it was committed to.
+///
+/// The merkle proof of every chunk is checked against `erasure_root` before decoding,
+/// and chunks whose proofs don't match are ignored, so enough valid chunks are needed
+/// among the rest. As chunks may each be valid and still decode to different data if
+/// they weren't all produced by the same encoding, the reconstructed data is re-encoded
+/// and its root compared with `erasure_root` too, failing with
+/// `Error::UnexpectedErasureRoot` on mismatch.
+///
+/// Works only up to 65536 validators, and `n_validators` must be non-zero.
+pub fn reconstruct_verified(n_validators: usize, erasure_root: &H256, chunks: &[ErasureChunk])
+	-> Result<AvailableData, Error>
+{
+	reconstruct_with_policy(n_validators, erasure_root, chunks, ProofPolicy::Strict)
+}
+
+/// Reconstruct the block data from erasure chunks like `reconstruct_verified`, checking
+/// the chunk proofs only if required by `policy`.
+///
+/// The root of the reconstructed data is always compared with `erasure_root`.
+///
+/// Works only up to 65536 validators, and `n_validators` must be non-zero.
+pub fn reconstruct_with_policy(
+	n_validators: usize,
+	erasure_root: &H256,
+	chunks: &[ErasureChunk],
+	policy: ProofPolicy,
+) -> Result<AvailableData, Error> {
+	let chunks: Vec<&ErasureChunk> = chunks.iter()
+		.filter(|chunk| match policy {
+			ProofPolicy::Strict => verify_chunk_against_root(chunk, erasure_root),
+			ProofPolicy::TrustRoot => true,
+		})
+		.collect();
+
+	let indexed = || chunks.iter().map(|chunk| (&chunk.chunk[..], chunk.index as usize));
+
+	// try the cheap path of concatenating the systematic chunks first,
+	// and only fall back to a full decode if some of them are missing. Any other
+	// error comes from the chunks we have, which a full decode would reuse.
+	let available_data = match reconstruct_from_systematic(n_validators, indexed()) {
+		Err(Error::NotEnoughChunks) => reconstruct(n_validators, indexed())?,
+		res => res?,
+	};
+
+	let (_, root) = available_data_to_chunks(n_validators, &available_data)?;
+	if &root != erasure_root {
+		return Err(Error::UnexpectedErasureRoot);
+	}
+
+	Ok(available_data)
+}
+
 /// An iterator that yields merkle branches and chunk data for all chunks to
 /// be sent to other validators.
 pub struct Branches<'a, I> {
@@ -270,6 +444,29 @@ pub fn branches<'a, I: 'a>(chunks: &'a [I]) -> Branches<'a, I>
 	}
 }
 
+/// Erasure-code the available data into one chunk per validator, each carrying a
+/// merkle proof of its inclusion under the returned erasure root.
+///
+/// Works only up to 65536 validators, and `n_validators` must be non-zero.
+pub fn available_data_to_chunks(n_validators: usize, available_data: &AvailableData)
+	-> Result<(Vec<ErasureChunk>, H256), Error>
+{
+	let chunks = obtain_chunks(n_validators, available_data)?;
+	let branches = branches(chunks.as_ref());
+	let root = branches.root();
+
+	let erasure_chunks = branches
+		.enumerate()
+		.map(|(index, (proof, chunk))| ErasureChunk {
+			chunk: chunk.to_vec(),
+			index: index as u32,
+			proof,
+		})
+		.collect();
+
+	Ok((erasure_chunks, root))
+}
+
 /// Verify a merkle branch, yielding the chunk hash meant to be present at that
 /// index.
 pub fn branch_hash(root: &H256, branch_nodes: &[Vec<u8>], index: usize) -> Result<H256, Error> {
@@ -345,6 +542,15 @@ mod tests {
 	use super::*;
 	use primitives::parachain::{BlockData, PoVBlock};
 
+	fn test_available_data() -> AvailableData {
+		AvailableData {
+			pov_block: PoVBlock {
+				block_data: BlockData((0..255).collect()),
+			},
+			omitted_validation: Default::default(),
+		}
+	}
+
 	#[test]
 	fn field_order_is_right_size() {
 		assert_eq!(MAX_VALIDATORS, 65536);
@@ -380,6 +586,34 @@ mod tests {
 		}));
 	}
 
+	#[test]
+	fn recovery_threshold_matches_data_shards() {
+		assert_eq!(recovery_threshold(0), Err(Error::EmptyValidators));
+		assert_eq!(recovery_threshold(1), Ok(1));
+		assert_eq!(recovery_threshold(2), Ok(1));
+		assert_eq!(recovery_threshold(3), Ok(1));
+		assert_eq!(recovery_threshold(4), Ok(2));
+		assert_eq!(recovery_threshold(10), Ok(4));
+		assert_eq!(recovery_threshold(100), Ok(34));
+		assert_eq!(recovery_threshold(MAX_VALIDATORS), Ok(21846));
+		assert_eq!(recovery_threshold(MAX_VALIDATORS + 1), Err(Error::TooManyValidators));
+	}
+
+	#[test]
+	fn coding_parameters_are_pinned() {
+		assert_eq!(coding_parameters(0), Err(Error::EmptyValidators));
+		assert_eq!(coding_parameters(1), Ok((1, 1)));
+		assert_eq!(coding_parameters(4), Ok((2, 4)));
+		assert_eq!(coding_parameters(10), Ok((4, 10)));
+		assert_eq!(coding_parameters(100), Ok((34, 100)));
+		assert_eq!(coding_parameters(MAX_VALIDATORS), Ok((21846, MAX_VALIDATORS)));
+
+		for n_validators in 1..100 {
+			let (data_shards, _) = coding_parameters(n_validators).unwrap();
+			assert_eq!(Ok(data_shards), recovery_threshold(n_validators));
+		}
+	}
+
 	#[test]
 	fn shard_len_is_reasonable() {
 		let mut params = CodeParams {
@@ -433,6 +667,59 @@ mod tests {
 		assert_eq!(reconstructed, available_data);
 	}
 
+	#[test]
+	fn reconstruction_from_unordered_chunks_works() {
+		let available_data = test_available_data();
+		let (chunks, root) = available_data_to_chunks(10, &available_data).unwrap();
+
+		let shuffled: Vec<_> = [9, 2, 7, 0, 5].iter().map(|&i| chunks[i].clone()).collect();
+
+		let reconstructed = reconstruct(
+			10,
+			shuffled.iter().map(|c| (&c.chunk[..], c.index as usize)),
+		).unwrap();
+		assert_eq!(reconstructed, available_data);
+
+		assert_eq!(reconstruct_verified(10, &root, &shuffled), Ok(available_data));
+	}
+
+	#[test]
+	fn systematic_reconstruction_works() {
+		let available_data = test_available_data();
+		let chunks = obtain_chunks(
+			10,
+			&available_data,
+		).unwrap();
+
+		// the first 4 chunks are systematic; others are ignored.
+		let reconstructed = reconstruct_from_systematic(
+			10,
+			[
+				(&*chunks[3], 3),
+				(&*chunks[8], 8),
+				(&*chunks[0], 0),
+				(&*chunks[2], 2),
+				(&*chunks[1], 1),
+			].iter().cloned(),
+		).unwrap();
+
+		assert_eq!(reconstructed, available_data);
+
+		// a missing systematic chunk means a full decode is needed.
+		assert_eq!(
+			reconstruct_from_systematic(
+				10,
+				[
+					(&*chunks[0], 0),
+					(&*chunks[1], 1),
+					(&*chunks[2], 2),
+					(&*chunks[9], 9),
+				].iter().cloned(),
+			),
+			Err(Error::NotEnoughChunks),
+		);
+	}
+
 	#[test]
 	fn construct_valid_branches() {
 		let pov_block = PoVBlock {
@@ -462,4 +749,128 @@ mod tests {
 			assert_eq!(branch_hash(&root, &proof, i).unwrap(), BlakeTwo256::hash(&chunks[i]));
 		}
 	}
+
+	#[test]
+	fn available_data_to_chunks_round_trips() {
+		let available_data = test_available_data();
+
+		let (chunks, root) = available_data_to_chunks(10, &available_data).unwrap();
+		assert_eq!(chunks.len(), 10);
+
+		for (i, chunk) in chunks.iter().enumerate() {
+			assert_eq!(chunk.index, i as u32);
+			assert_eq!(
+				branch_hash(&root, &chunk.proof, i).unwrap(),
+				BlakeTwo256::hash(&chunk.chunk),
+			);
+		}
+
+		let reconstructed = reconstruct(
+			10,
+			chunks.iter().skip(6).map(|c| (&c.chunk[..], c.index as usize)),
+		).unwrap();
+
+		assert_eq!(reconstructed, available_data);
+	}
+
+	#[test]
+	fn chunks_are_verified_against_root() {
+		let available_data = test_available_data();
+
+		let (mut chunks, root) = available_data_to_chunks(10, &available_data).unwrap();
+
+		assert_eq!(verify_chunks_against_root(&chunks, &root), Ok(()));
+		assert_eq!(verify_chunks_against_root(&[], &root), Ok(()));
+
+		chunks[2].chunk[0] ^= 1;
+		chunks[7].proof = chunks[6].proof.clone();
+		chunks[9].index = 3;
+
+		assert_eq!(verify_chunks_against_root(&chunks, &root), Err(vec![2, 7, 3]));
+	}
+
+	#[test]
+	fn verified_reconstruction_checks_proofs_and_root() {
+		let available_data = test_available_data();
+
+		let (chunks, root) = available_data_to_chunks(10, &available_data).unwrap();
+
+		assert_eq!(reconstruct_verified(10, &root, &chunks[5..9]), Ok(available_data.clone()));
+
+		assert_eq!(reconstruct_verified(10, &root, &chunks[5..8]), Err(Error::NotEnoughChunks));
+
+		// chunks with bad proofs are ignored, so they don't count towards the threshold,
+		// but don't prevent reconstruction from the others either.
+		let mut bad_proof = chunks[5..10].to_vec();
+		bad_proof[0].proof = chunks[0].proof.clone();
+		assert_eq!(reconstruct_verified(10, &root, &bad_proof[..4]), Err(Error::NotEnoughChunks));
+		assert_eq!(reconstruct_verified(10, &root, &bad_proof), Ok(available_data.clone()));
+
+		// chunks of some other data don't match the root at all.
+		let other_data = AvailableData {
+			pov_block: PoVBlock { block_data: BlockData(vec![1; 255]) },
+			omitted_validation: Default::default(),
+		};
+		let (other_chunks, _) = available_data_to_chunks(10, &other_data).unwrap();
+		assert_eq!(
+			reconstruct_verified(10, &root, &other_chunks[5..9]),
+			Err(Error::NotEnoughChunks),
+		);
+
+		// a tampered encoding committed to its own root: every proof verifies, but the
+		// data it decodes to encodes to a different root.
+		let mut tampered = obtain_chunks(10, &available_data).unwrap();
+		tampered[1][0] ^= 1;
+
+		let tampered_branches = branches(&tampered[..]);
+		let tampered_root = tampered_branches.root();
+		let tampered_chunks: Vec<_> = tampered_branches
+			.enumerate()
+			.map(|(index, (proof, chunk))| ErasureChunk {
+				chunk: chunk.to_vec(),
+				index: index as u32,
+				proof,
+			})
+			.collect();
+
+		assert_eq!(verify_chunks_against_root(&tampered_chunks, &tampered_root), Ok(()));
+		assert_eq!(
+			reconstruct_verified(10, &tampered_root, &tampered_chunks[..4]),
+			Err(Error::UnexpectedErasureRoot),
+		);
+	}
+
+	#[test]
+	fn proof_policies_detect_corruption() {
+		let available_data = test_available_data();
+		let (chunks, root) = available_data_to_chunks(10, &available_data).unwrap();
+
+		let mut bad_proof = chunks[..5].to_vec();
+		bad_proof[1].proof = Vec::new();
+
+		assert_eq!(
+			reconstruct_with_policy(10, &root, &bad_proof[..4], ProofPolicy::Strict),
+			Err(Error::NotEnoughChunks),
+		);
+		assert_eq!(
+			reconstruct_with_policy(10, &root, &bad_proof, ProofPolicy::Strict),
+			Ok(available_data.clone()),
+		);
+		assert_eq!(
+			reconstruct_with_policy(10, &root, &bad_proof[..4], ProofPolicy::TrustRoot),
+			Ok(available_data.clone()),
+		);
+
+		let mut corrupt = chunks[..5].to_vec();
+		corrupt[1].chunk[0] ^= 1;
+
+		assert_eq!(
+			reconstruct_with_policy(10, &root, &corrupt, ProofPolicy::Strict),
+			Ok(available_data),
+		);
+		assert_eq!(
+			reconstruct_with_policy(10, &root, &corrupt[..4], ProofPolicy::TrustRoot),
+			Err(Error::UnexpectedErasureRoot),
+		);
+	}
 }
@@ -37,7 +37,7 @@ use polkadot_primitives::Hash;
 
 use std::collections::HashMap;
 
-use log::warn;
+use log::{debug, warn};
 
 use super::{
 	cost, benefit, attestation_topic, MAX_CHAIN_HEADS, LeavesVec,
@@ -248,7 +248,15 @@ impl View {
 						let topic = attestation_topic(message.relay_chain_leaf);
 						(GossipValidationResult::ProcessAndKeep(topic), benefit)
 					}
-					Err(()) => (GossipValidationResult::Discard, cost::BAD_SIGNATURE),
+					Err(e) => {
+						debug!(
+							target: "network",
+							"Discarding statement from validator {}: {:?}",
+							message.signed_statement.sender,
+							e,
+						);
+						(GossipValidationResult::Discard, cost::BAD_SIGNATURE)
+					}
 				}
 			}
 		}
@@ -57,7 +57,7 @@ use sc_network_gossip::{
 	ValidationResult as GossipValidationResult,
 	ValidatorContext, MessageIntent,
 };
-use polkadot_validation::{SignedStatement};
+use polkadot_validation::{SignedStatement, CheckSignatureError};
 use polkadot_primitives::{Block, Hash};
 use polkadot_primitives::parachain::{
 	ParachainHost, ValidatorId, ErasureChunk as PrimitiveChunk, SigningContext, PoVBlock,
@@ -491,25 +491,12 @@ pub(crate) struct MessageValidationData {
 
 impl MessageValidationData {
 	// check a statement's signature.
-	fn check_statement(&self, statement: &SignedStatement) -> Result<(), ()> {
-		let sender = match self.authorities.get(statement.sender as usize) {
-			Some(val) => val,
-			None => return Err(()),
-		};
-
-		let good = self.authorities.contains(&sender) &&
-			::polkadot_validation::check_statement(
-				&statement.statement,
-				&statement.signature,
-				sender.clone(),
-				&self.signing_context,
-			);
-
-		if good {
-			Ok(())
-		} else {
-			Err(())
-		}
+	fn check_statement(&self, statement: &SignedStatement) -> Result<(), CheckSignatureError> {
+		::polkadot_validation::check_signed_statement(
+			statement,
+			&self.authorities,
+			&self.signing_context,
+		)
 	}
 }
 
@@ -553,13 +540,7 @@ impl<C: ?Sized + ChainContext> Inner<C> {
 	{
 		if let Some(store) = &self.availability_store {
 			if let Some(receipt) = store.get_candidate(&msg.candidate_hash) {
-				let chunk_hash = erasure::branch_hash(
-					&receipt.commitments.erasure_root,
-					&msg.chunk.proof,
-					msg.chunk.index as usize
-				);
-
-				if chunk_hash != Ok(BlakeTwo256::hash(&msg.chunk.chunk)) {
+				if !erasure::verify_chunk_against_root(&msg.chunk, &receipt.commitments.erasure_root) {
 					(
 						GossipValidationResult::Discard,
 						cost::ERASURE_CHUNK_WRONG_ROOT
@@ -157,7 +157,7 @@ impl<T: Encode + Decode + Default> AccountIdConversion<T> for Id {
 
 /// Which origin a parachain's message to the relay chain should be dispatched from.
 #[derive(Clone, PartialEq, Eq, Encode, Decode)]
-#[cfg_attr(feature = "std", derive(Debug))]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize, Debug))]
 #[repr(u8)]
 pub enum ParachainDispatchOrigin {
 	/// As a simple `Origin::Signed`, using `ParaId::account_id` as its value. This is good when
@@ -190,7 +190,7 @@ impl sp_std::convert::TryFrom<u8> for ParachainDispatchOrigin {
 	any(feature = "std", feature = "wasm-api"),
 	derive(sp_runtime_interface::pass_by::PassByCodec,
 ))]
-#[cfg_attr(feature = "std", derive(Debug))]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize, Debug))]
 pub struct UpwardMessage {
 	/// The origin for the message to be sent from.
 	pub origin: ParachainDispatchOrigin,
@@ -37,6 +37,7 @@ pub use polkadot_parachain::primitives::{
 	Id, ParachainDispatchOrigin, LOWEST_USER_ID, UpwardMessage, HeadData, BlockData,
 	ValidationCode,
 };
+pub use sp_staking::SessionIndex;
 
 /// The key type ID for a collator key.
 pub const COLLATOR_KEY_TYPE_ID: KeyTypeId = KeyTypeId(*b"coll");
@@ -210,7 +211,7 @@ pub struct LocalValidationData {
 
 /// Commitments made in a `CandidateReceipt`. Many of these are outputs of validation.
 #[derive(PartialEq, Eq, Clone, Encode, Decode)]
-#[cfg_attr(feature = "std", derive(Debug, Default))]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize, Debug, Default))]
 pub struct CandidateCommitments {
 	/// Fees paid from the chain to the relay chain validators.
 	pub fees: Balance,
@@ -358,7 +359,7 @@ pub struct OmittedValidationData {
 /// When submitting to the relay-chain, this data should be omitted as it can
 /// be re-generated from relay-chain state.
 #[derive(PartialEq, Eq, Clone, Encode, Decode)]
-#[cfg_attr(feature = "std", derive(Debug, Default))]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize, Debug, Default))]
 pub struct AbridgedCandidateReceipt {
 	/// The ID of the parachain this is a candidate for.
 	pub parachain_index: Id,
@@ -372,6 +373,7 @@ pub struct AbridgedCandidateReceipt {
 	/// The collator's relay-chain account ID
 	pub collator: CollatorId,
 	/// Signature on blake2-256 of the block data by collator.
+	#[cfg_attr(feature = "std", serde(with = "serde_helpers::encoded"))]
 	pub signature: CollatorSignature,
 	/// The hash of the pov-block.
 	pub pov_block_hash: Hash,
@@ -579,8 +581,8 @@ pub struct Activity(#[cfg_attr(feature = "std", serde(with="bytes"))] pub Vec<u8
 
 /// Statements that can be made about parachain candidates. These are the
 /// actual values that are signed.
-#[derive(Clone, PartialEq, Eq, Encode, Decode)]
-#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Clone, PartialEq, Eq, Hash, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize, Debug))]
 pub enum Statement {
 	/// Proposal of a parachain candidate.
 	#[codec(index = "1")]
@@ -596,28 +598,30 @@ pub enum Statement {
 /// An either implicit or explicit attestation to the validity of a parachain
 /// candidate.
 #[derive(Clone, Eq, PartialEq, Decode, Encode, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
 pub enum ValidityAttestation {
 	/// Implicit validity attestation by issuing.
 	/// This corresponds to issuance of a `Candidate` statement.
 	#[codec(index = "1")]
-	Implicit(ValidatorSignature),
+	Implicit(#[cfg_attr(feature = "std", serde(with = "serde_helpers::encoded"))] ValidatorSignature),
 	/// An explicit attestation. This corresponds to issuance of a
 	/// `Valid` statement.
 	#[codec(index = "2")]
-	Explicit(ValidatorSignature),
+	Explicit(#[cfg_attr(feature = "std", serde(with = "serde_helpers::encoded"))] ValidatorSignature),
 }
 
 /// A type returned by runtime with current session index and a parent hash.
 #[derive(Clone, Eq, PartialEq, Default, Decode, Encode, RuntimeDebug)]
 pub struct SigningContext {
 	/// Current session index.
-	pub session_index: sp_staking::SessionIndex,
+	pub session_index: SessionIndex,
 	/// Hash of the parent.
 	pub parent_hash: Hash,
 }
 
 /// An attested candidate. This is submitted to the relay chain by a block author.
-#[derive(Clone, PartialEq, Decode, Encode, RuntimeDebug)]
+#[derive(Clone, PartialEq, Eq, Decode, Encode, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
 pub struct AttestedCandidate {
 	/// The candidate data. This is abridged, because the omitted data
 	/// is already present within the relay chain state.
@@ -625,10 +629,162 @@ pub struct AttestedCandidate {
 	/// Validity attestations.
 	pub validity_votes: Vec<ValidityAttestation>,
 	/// Indices of the corresponding validity votes.
+	#[cfg_attr(feature = "std", serde(with = "serde_helpers::bits"))]
 	pub validator_indices: BitVec<bitvec::order::Lsb0, u8>,
 }
 
 impl AttestedCandidate {
+	/// Create a new attested candidate.
+	pub fn new(
+		candidate: AbridgedCandidateReceipt,
+		validity_votes: Vec<ValidityAttestation>,
+		validator_indices: BitVec<bitvec::order::Lsb0, u8>,
+	) -> Self {
+		AttestedCandidate {
+			candidate,
+			validity_votes,
+			validator_indices,
+		}
+	}
+
+	/// Decode an attested candidate from untrusted input, refusing encodings which
+	/// claim more than `max_votes` validity votes or a `validator_indices` bitfield
+	/// longer than `max_validators` bits.
+	///
+	/// The claimed numbers of votes and validator indices are checked before anything
+	/// is allocated for them. The candidate receipt is decoded as usual, so the lengths
+	/// of its own vectors are not bounded here; callers should also bound the size of
+	/// the input itself. Any input after the bitfield is left unread, so candidates can
+	/// be decoded in sequence; see `decode_all_with_limits` to reject trailing bytes.
+	pub fn decode_with_limits<I: parity_scale_codec::Input>(
+		input: &mut I,
+		max_votes: usize,
+		max_validators: usize,
+	) -> Result<Self, parity_scale_codec::Error> {
+		use parity_scale_codec::Compact;
+
+		let candidate = AbridgedCandidateReceipt::decode(input)?;
+
+		let n_votes = <Compact<u32>>::decode(input)?.0 as usize;
+		if n_votes > max_votes {
+			return Err("Too many validity votes in attested candidate".into());
+		}
+
+		let mut validity_votes = Vec::with_capacity(n_votes);
+		for _ in 0..n_votes {
+			validity_votes.push(ValidityAttestation::decode(input)?);
+		}
+
+		let n_bits = <Compact<u32>>::decode(input)?.0 as usize;
+		if n_bits > max_validators {
+			return Err("Too many validator indices in attested candidate".into());
+		}
+
+		let mut bytes = vec![0u8; (n_bits + 7) / 8];
+		input.read(&mut bytes[..])?;
+
+		let mut validator_indices = BitVec::from_vec(bytes);
+		validator_indices.truncate(n_bits);
+
+		Ok(AttestedCandidate {
+			candidate,
+			validity_votes,
+			validator_indices,
+		})
+	}
+
+	/// Decode an attested candidate like `decode_with_limits`, additionally requiring
+	/// `input` to hold exactly one attested candidate with no trailing bytes.
+	pub fn decode_all_with_limits(
+		mut input: &[u8],
+		max_votes: usize,
+		max_validators: usize,
+	) -> Result<Self, parity_scale_codec::Error> {
+		let attested = Self::decode_with_limits(&mut input, max_votes, max_validators)?;
+
+		if !input.is_empty() {
+			return Err("Trailing bytes after attested candidate".into());
+		}
+
+		Ok(attested)
+	}
+
+	/// Pair each validity vote with the validator that issued it, in order.
+	///
+	/// `validators` is the validator set that `validator_indices` refers to.
+	/// Fails if the number of indicated validators doesn't match the number of
+	/// votes, or if an indicated validator is not part of `validators`.
+	pub fn attesting_validators<'a>(&'a self, validators: &'a [ValidatorId])
+		-> Result<impl Iterator<Item=(&'a ValidatorId, &'a ValidityAttestation)> + 'a, AttestationError>
+	{
+		self.check_vote_count()?;
+
+		if let Some(index) = self.attesting_indices().find(|i| *i as usize >= validators.len()) {
+			return Err(AttestationError::ValidatorIndexOutOfBounds(index));
+		}
+
+		Ok(self.attesting_indices()
+			.map(move |i| &validators[i as usize])
+			.zip(self.validity_votes.iter()))
+	}
+
+	/// Check that there is exactly one validity vote for each validator indicated
+	/// by `validator_indices`, that every such validator is a member of `group`,
+	/// and that every vote is correctly signed by that validator.
+	///
+	/// `validators` is the validator set that `validator_indices` refers to, and
+	/// `group` holds the indices into it of the validators assigned to the
+	/// candidate's parachain. These are the checks the runtime performs on inclusion.
+	pub fn validate(
+		&self,
+		validators: &[ValidatorId],
+		group: &[ValidatorIndex],
+		signing_context: &SigningContext,
+	) -> Result<(), AttestationError> {
+		use runtime_primitives::traits::AppVerify;
+
+		let candidate_hash = self.candidate.hash();
+		let attesting = self.attesting_indices().zip(self.attesting_validators(validators)?);
+
+		for (validator_index, (validator, vote)) in attesting {
+			if !group.contains(&validator_index) {
+				return Err(AttestationError::NotInGroup(validator_index));
+			}
+
+			let (statement, signature) = match *vote {
+				ValidityAttestation::Implicit(ref sig) => (Statement::Candidate(candidate_hash), sig),
+				ValidityAttestation::Explicit(ref sig) => (Statement::Valid(candidate_hash), sig),
+			};
+
+			let payload = localized_payload(statement, signing_context);
+			if !signature.verify(&payload[..], validator) {
+				return Err(AttestationError::BadSignature(validator_index));
+			}
+		}
+
+		Ok(())
+	}
+
+	// the indices of all validators indicated by `validator_indices`, in ascending order.
+	fn attesting_indices<'a>(&'a self) -> impl Iterator<Item=ValidatorIndex> + 'a {
+		self.validator_indices.iter()
+			.enumerate()
+			.filter(|(_, bit)| **bit)
+			.map(|(i, _)| i as ValidatorIndex)
+	}
+
+	fn check_vote_count(&self) -> Result<(), AttestationError> {
+		let n_indices = self.attesting_indices().count();
+		if n_indices != self.validity_votes.len() {
+			return Err(AttestationError::VoteCountMismatch {
+				indices: n_indices,
+				votes: self.validity_votes.len(),
+			});
+		}
+
+		Ok(())
+	}
+
 	/// Get the candidate.
 	pub fn candidate(&self) -> &AbridgedCandidateReceipt {
 		&self.candidate
@@ -640,6 +796,70 @@ impl AttestedCandidate {
 	}
 }
 
+/// Errors that can occur when validating an `AttestedCandidate`.
+#[derive(Clone, PartialEq, Eq, RuntimeDebug)]
+pub enum AttestationError {
+	/// The number of validators indicated doesn't match the number of validity votes.
+	VoteCountMismatch {
+		/// The number of set bits in `validator_indices`.
+		indices: usize,
+		/// The number of validity votes.
+		votes: usize,
+	},
+	/// An indicated validator is not part of the validator set.
+	ValidatorIndexOutOfBounds(ValidatorIndex),
+	/// An indicated validator is not a member of the candidate's validator group.
+	NotInGroup(ValidatorIndex),
+	/// The validity vote of the given validator has an invalid signature.
+	BadSignature(ValidatorIndex),
+}
+
+/// The payload a validator signs for a statement: the encoded statement
+/// followed by the encoded signing context.
+pub fn localized_payload(statement: Statement, signing_context: &SigningContext) -> Vec<u8> {
+	let mut encoded = statement.encode();
+	signing_context.using_encoded(|s| encoded.extend(s));
+	encoded
+}
+
+/// Serde support for field types which don't implement it themselves.
+#[cfg(feature = "std")]
+pub mod serde_helpers {
+	/// Serialize a value as its hex-encoded SCALE encoding.
+	pub mod encoded {
+		use parity_scale_codec::{Encode, Decode};
+		use serde::{Serializer, Deserializer, de::Error};
+		use primitives::bytes;
+
+		/// Serialize `value` as its hex-encoded SCALE encoding.
+		pub fn serialize<T: Encode, S: Serializer>(value: &T, serializer: S) -> Result<S::Ok, S::Error> {
+			bytes::serialize(&value.encode(), serializer)
+		}
+
+		/// Deserialize a value from its hex-encoded SCALE encoding.
+		pub fn deserialize<'de, T: Decode, D: Deserializer<'de>>(deserializer: D) -> Result<T, D::Error> {
+			let encoded = bytes::deserialize(deserializer)?;
+			T::decode(&mut &encoded[..]).map_err(|e| D::Error::custom(format!("{:?}", e)))
+		}
+	}
+
+	/// Serialize a bitfield as a sequence of booleans.
+	pub mod bits {
+		use bitvec::{vec::BitVec, order::Lsb0};
+		use serde::{Serialize, Serializer, Deserialize, Deserializer};
+
+		/// Serialize `bits` as a sequence of booleans.
+		pub fn serialize<S: Serializer>(bits: &BitVec<Lsb0, u8>, serializer: S) -> Result<S::Ok, S::Error> {
+			bits.iter().map(|b| *b).collect::<Vec<bool>>().serialize(serializer)
+		}
+
+		/// Deserialize a bitfield from a sequence of booleans.
+		pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<BitVec<Lsb0, u8>, D::Error> {
+			Ok(Vec::<bool>::deserialize(deserializer)?.into_iter().collect())
+		}
+	}
+}
+
 /// A fee schedule for messages. This is a linear function in the number of bytes of a message.
 #[derive(PartialEq, Eq, PartialOrd, Hash, Default, Clone, Copy, Encode, Decode)]
 #[cfg_attr(feature = "std", derive(Serialize, Deserialize, Debug))]
@@ -697,6 +917,7 @@ pub mod id {
 #[cfg(test)]
 mod tests {
 	use super::*;
+	use primitives::{Pair, sr25519};
 
 	#[test]
 	fn balance_bigger_than_usize() {
@@ -718,4 +939,185 @@ mod tests {
 			&[2; 32].into(),
 		);
 	}
+
+	fn signing_context() -> SigningContext {
+		SigningContext {
+			session_index: 1,
+			parent_hash: [2; 32].into(),
+		}
+	}
+
+	fn validator_pairs(n: u8) -> Vec<ValidatorPair> {
+		(0..n).map(|i| ValidatorPair::from_seed(&[i; 32])).collect()
+	}
+
+	#[test]
+	fn attested_candidate_validation() {
+		let keys = validator_pairs(4);
+		let validators: Vec<ValidatorId> = keys.iter().map(|k| k.public()).collect();
+		let group = [1, 2, 3];
+		let signing_context = signing_context();
+
+		let candidate = AbridgedCandidateReceipt::default();
+		let candidate_hash = candidate.hash();
+
+		let implicit = keys[1].sign(&localized_payload(Statement::Candidate(candidate_hash), &signing_context));
+		let explicit = keys[3].sign(&localized_payload(Statement::Valid(candidate_hash), &signing_context));
+		let validator_indices = bitvec::bitvec![bitvec::order::Lsb0, u8; 0, 1, 0, 1];
+
+		let attested = AttestedCandidate::new(
+			candidate.clone(),
+			vec![
+				ValidityAttestation::Implicit(implicit.clone()),
+				ValidityAttestation::Explicit(explicit.clone()),
+			],
+			validator_indices.clone(),
+		);
+		assert_eq!(attested.validate(&validators, &group, &signing_context), Ok(()));
+
+		// correctly signed, but validator 1 isn't assigned to the parachain.
+		assert_eq!(
+			attested.validate(&validators, &[2, 3], &signing_context),
+			Err(AttestationError::NotInGroup(1)),
+		);
+
+		let missing_vote = AttestedCandidate::new(
+			candidate.clone(),
+			vec![ValidityAttestation::Implicit(implicit)],
+			validator_indices.clone(),
+		);
+		assert_eq!(
+			missing_vote.validate(&validators, &group, &signing_context),
+			Err(AttestationError::VoteCountMismatch { indices: 2, votes: 1 }),
+		);
+
+		// validator 3's signature in validator 1's place.
+		let bad_signature = AttestedCandidate::new(
+			candidate,
+			vec![
+				ValidityAttestation::Explicit(explicit.clone()),
+				ValidityAttestation::Explicit(explicit),
+			],
+			validator_indices,
+		);
+		assert_eq!(
+			bad_signature.validate(&validators, &group, &signing_context),
+			Err(AttestationError::BadSignature(1)),
+		);
+	}
+
+	#[test]
+	fn attested_candidate_clone_is_equal() {
+		let attested = AttestedCandidate::new(
+			Default::default(),
+			vec![ValidityAttestation::Implicit(Default::default())],
+			bitvec::bitvec![bitvec::order::Lsb0, u8; 0, 1],
+		);
+
+		let cloned = attested.clone();
+		assert_eq!(cloned, attested);
+
+		let mut changed = attested.clone();
+		changed.validator_indices.push(true);
+		assert_ne!(changed, attested);
+	}
+
+	#[test]
+	fn attesting_validators_pairs_sparse_indices() {
+		let validators: Vec<ValidatorId> = validator_pairs(6).iter().map(|k| k.public()).collect();
+		let votes: Vec<_> = (0..3u8)
+			.map(|i| ValidityAttestation::Explicit(ValidatorSignature::from(sr25519::Signature([i; 64]))))
+			.collect();
+
+		let attested = AttestedCandidate::new(
+			Default::default(),
+			votes.clone(),
+			bitvec::bitvec![bitvec::order::Lsb0, u8; 0, 1, 0, 1, 1],
+		);
+
+		let pairs: Vec<_> = attested.attesting_validators(&validators).unwrap().collect();
+		assert_eq!(pairs, vec![
+			(&validators[1], &votes[0]),
+			(&validators[3], &votes[1]),
+			(&validators[4], &votes[2]),
+		]);
+
+		assert_eq!(
+			attested.attesting_validators(&validators[..4]).err(),
+			Some(AttestationError::ValidatorIndexOutOfBounds(4)),
+		);
+
+		let too_few_votes = AttestedCandidate::new(
+			Default::default(),
+			votes[..2].to_vec(),
+			bitvec::bitvec![bitvec::order::Lsb0, u8; 0, 1, 0, 1, 1],
+		);
+		assert_eq!(
+			too_few_votes.attesting_validators(&validators).err(),
+			Some(AttestationError::VoteCountMismatch { indices: 3, votes: 2 }),
+		);
+	}
+
+	#[test]
+	fn decode_with_limits_bounds_claimed_lengths() {
+		use parity_scale_codec::Compact;
+
+		let votes: Vec<_> = (0..3u8)
+			.map(|i| ValidityAttestation::Explicit(ValidatorSignature::from(sr25519::Signature([i; 64]))))
+			.collect();
+		let attested = AttestedCandidate::new(
+			Default::default(),
+			votes,
+			bitvec::bitvec![bitvec::order::Lsb0, u8; 0, 1, 0, 1, 1, 0, 0, 0, 0, 1],
+		);
+		let encoded = attested.encode();
+
+		assert_eq!(AttestedCandidate::decode_with_limits(&mut &encoded[..], 3, 10).ok(), Some(attested.clone()));
+		assert!(AttestedCandidate::decode_with_limits(&mut &encoded[..], 2, 10).is_err());
+		assert!(AttestedCandidate::decode_with_limits(&mut &encoded[..], 3, 9).is_err());
+
+		// candidates decode in sequence, but not as a single value with trailing bytes.
+		let mut two = encoded.clone();
+		two.extend(&encoded);
+		let mut input = &two[..];
+		assert_eq!(AttestedCandidate::decode_with_limits(&mut input, 3, 10).ok(), Some(attested.clone()));
+		assert_eq!(AttestedCandidate::decode_with_limits(&mut input, 3, 10).ok(), Some(attested.clone()));
+		assert!(input.is_empty());
+
+		assert_eq!(AttestedCandidate::decode_all_with_limits(&encoded, 3, 10).ok(), Some(attested));
+		assert!(AttestedCandidate::decode_all_with_limits(&two, 3, 10).is_err());
+
+		// a candidate claiming an absurd number of votes, with nothing behind it.
+		let mut absurd = AbridgedCandidateReceipt::default().encode();
+		Compact(u32::max_value()).encode_to(&mut absurd);
+		assert!(AttestedCandidate::decode_with_limits(&mut &absurd[..], 100, 100).is_err());
+	}
+
+	#[test]
+	fn attested_candidate_json_roundtrip() {
+		let collator = sr25519::Pair::from_seed(&[1; 32]);
+		let mut candidate = AbridgedCandidateReceipt::default();
+		candidate.collator = collator.public().into();
+		candidate.signature = collator.sign(&[1, 2, 3]).into();
+		candidate.commitments.upward_messages.push(UpwardMessage {
+			origin: ParachainDispatchOrigin::Signed,
+			data: vec![4, 5, 6],
+		});
+
+		let attested = AttestedCandidate::new(
+			candidate,
+			vec![
+				ValidityAttestation::Implicit(ValidatorSignature::from(sr25519::Signature([1; 64]))),
+				ValidityAttestation::Explicit(ValidatorSignature::from(sr25519::Signature([2; 64]))),
+			],
+			bitvec::bitvec![bitvec::order::Lsb0, u8; 0, 1, 0, 0, 0, 0, 0, 0, 0, 1],
+		);
+
+		let json = sp_serializer::to_string_pretty(&attested);
+		assert_eq!(sp_serializer::from_str::<AttestedCandidate>(&json).unwrap(), attested);
+
+		let statement = Statement::Valid(attested.candidate.hash());
+		let json = sp_serializer::to_string_pretty(&statement);
+		assert_eq!(sp_serializer::from_str::<Statement>(&json).unwrap(), statement);
+	}
 }
@@ -45,7 +45,7 @@ use primitives::{
 		UpwardMessage, ValidatorId, ActiveParas, CollatorId, Retriable, OmittedValidationData,
 		CandidateReceipt, GlobalValidationSchedule, AbridgedCandidateReceipt,
 		LocalValidationData, Scheduling, ValidityAttestation, NEW_HEADS_IDENTIFIER, PARACHAIN_KEY_TYPE_ID,
-		ValidatorSignature, SigningContext, HeadData, ValidationCode,
+		ValidatorSignature, SigningContext, HeadData, ValidationCode, localized_payload,
 	},
 };
 use frame_support::{
@@ -675,15 +675,6 @@ fn majority_of(list_len: usize) -> usize {
 	list_len / 2 + list_len % 2
 }
 
-fn localized_payload(
-	statement: Statement,
-	signing_context: &SigningContext,
-) -> Vec<u8> {
-	let mut encoded = statement.encode();
-	signing_context.using_encoded(|s| encoded.extend(s));
-	encoded
-}
-
 impl<T: Trait> Module<T> {
 	/// Initialize the state of a new parachain/parathread.
 	pub fn initialize_para(
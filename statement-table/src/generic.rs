@@ -29,6 +29,7 @@ use std::hash::Hash;
 use std::fmt::Debug;
 
 use codec::{Encode, Decode};
+use serde::{Serialize, Deserialize};
 
 /// Context for the statement table.
 pub trait Context {
@@ -58,7 +59,7 @@ pub trait Context {
 }
 
 /// Statements circulated among peers.
-#[derive(PartialEq, Eq, Debug, Clone, Encode, Decode)]
+#[derive(PartialEq, Eq, Debug, Clone, Encode, Decode, Serialize, Deserialize)]
 pub enum Statement<C, D> {
 	/// Broadcast by an authority to indicate that this is his candidate for
 	/// inclusion.
@@ -76,12 +77,46 @@ pub enum Statement<C, D> {
 	Invalid(D),
 }
 
+impl<C, D> Statement<C, D> {
+	/// Get the candidate, if this is a `Candidate` statement.
+	pub fn expect_candidate(&self) -> Option<&C> {
+		match *self {
+			Statement::Candidate(ref c) => Some(c),
+			_ => None,
+		}
+	}
+
+	/// Get the digest of the candidate, if this is a `Valid` statement.
+	pub fn expect_valid(&self) -> Option<&D> {
+		match *self {
+			Statement::Valid(ref d) => Some(d),
+			_ => None,
+		}
+	}
+
+	/// Get the digest of the candidate, if this is an `Invalid` statement.
+	pub fn expect_invalid(&self) -> Option<&D> {
+		match *self {
+			Statement::Invalid(ref d) => Some(d),
+			_ => None,
+		}
+	}
+}
+
 /// A signed statement.
-#[derive(PartialEq, Eq, Debug, Clone, Encode, Decode)]
+///
+/// Signatures are serialized through their SCALE encoding, since signature types
+/// generally don't implement serde themselves.
+#[derive(PartialEq, Eq, Debug, Clone, Encode, Decode, Serialize, Deserialize)]
+#[serde(bound(
+	serialize = "C: Serialize, D: Serialize, V: Serialize, S: Encode",
+	deserialize = "C: Deserialize<'de>, D: Deserialize<'de>, V: Deserialize<'de>, S: Decode",
+))]
 pub struct SignedStatement<C, D, V, S> {
 	/// The statement.
 	pub statement: Statement<C, D>,
 	/// The signature.
+	#[serde(with = "primitives::parachain::serde_helpers::encoded")]
 	pub signature: S,
 	/// The sender.
 	pub sender: V,
@@ -91,29 +126,35 @@ pub struct SignedStatement<C, D, V, S> {
 ///
 /// Since there are three possible ways to vote, a double vote is possible in
 /// three possible combinations (unordered)
-#[derive(PartialEq, Eq, Debug, Clone)]
+#[derive(PartialEq, Eq, Debug, Clone, Encode, Decode)]
 pub enum ValidityDoubleVote<C, D, S> {
 	/// Implicit vote by issuing and explicitly voting validity.
+	#[codec(index = "1")]
 	IssuedAndValidity((C, S), (D, S)),
 	/// Implicit vote by issuing and explicitly voting invalidity
+	#[codec(index = "2")]
 	IssuedAndInvalidity((C, S), (D, S)),
 	/// Direct votes for validity and invalidity
+	#[codec(index = "3")]
 	ValidityAndInvalidity(D, S, S),
 }
 
 /// Misbehavior: multiple signatures on same statement.
-#[derive(PartialEq, Eq, Debug, Clone)]
+#[derive(PartialEq, Eq, Debug, Clone, Encode, Decode)]
 pub enum DoubleSign<C, D, S> {
 	/// On candidate.
+	#[codec(index = "1")]
 	Candidate(C, S, S),
 	/// On validity.
+	#[codec(index = "2")]
 	Validity(D, S, S),
 	/// On invalidity.
+	#[codec(index = "3")]
 	Invalidity(D, S, S),
 }
 
 /// Misbehavior: declaring multiple candidates.
-#[derive(PartialEq, Eq, Debug, Clone)]
+#[derive(PartialEq, Eq, Debug, Clone, Encode, Decode)]
 pub struct MultipleCandidates<C, S> {
 	/// The first candidate seen.
 	pub first: (C, S),
@@ -122,7 +163,7 @@ pub struct MultipleCandidates<C, S> {
 }
 
 /// Misbehavior: submitted statement for wrong group.
-#[derive(PartialEq, Eq, Debug, Clone)]
+#[derive(PartialEq, Eq, Debug, Clone, Encode, Decode)]
 pub struct UnauthorizedStatement<C, D, V, S> {
 	/// A signed statement which was submitted without proper authority.
 	pub statement: SignedStatement<C, D, V, S>,
@@ -130,15 +171,22 @@ pub struct UnauthorizedStatement<C, D, V, S> {
 
 /// Different kinds of misbehavior. All of these kinds of malicious misbehavior
 /// are easily provable and extremely disincentivized.
-#[derive(PartialEq, Eq, Debug, Clone)]
+///
+/// The codec indices of the variants are fixed so that reports can be
+/// gossiped and stored without the encoding shifting as variants are added.
+#[derive(PartialEq, Eq, Debug, Clone, Encode, Decode)]
 pub enum Misbehavior<C, D, V, S> {
 	/// Voted invalid and valid on validity.
+	#[codec(index = "1")]
 	ValidityDoubleVote(ValidityDoubleVote<C, D, S>),
 	/// Submitted multiple candidates.
+	#[codec(index = "2")]
 	MultipleCandidates(MultipleCandidates<C, S>),
 	/// Submitted a message that was unauthorized.
+	#[codec(index = "3")]
 	UnauthorizedStatement(UnauthorizedStatement<C, D, V, S>),
 	/// Submitted two valid signatures for the same message.
+	#[codec(index = "4")]
 	DoubleSign(DoubleSign<C, D, S>),
 }
 
@@ -1094,4 +1142,54 @@ mod tests {
 		assert_eq!(summary.group_id, GroupId(2));
 		assert_eq!(summary.validity_votes, 2);
 	}
+
+	#[test]
+	fn statement_kind_accessors() {
+		let candidate: Statement<Candidate, Digest> = Statement::Candidate(Candidate(1, 2));
+		let valid: Statement<Candidate, Digest> = Statement::Valid(Digest(2));
+		let invalid: Statement<Candidate, Digest> = Statement::Invalid(Digest(2));
+
+		assert_eq!(candidate.expect_candidate(), Some(&Candidate(1, 2)));
+		assert_eq!(valid.expect_candidate(), None);
+		assert_eq!(invalid.expect_candidate(), None);
+
+		assert_eq!(candidate.expect_valid(), None);
+		assert_eq!(valid.expect_valid(), Some(&Digest(2)));
+		assert_eq!(invalid.expect_valid(), None);
+
+		assert_eq!(candidate.expect_invalid(), None);
+		assert_eq!(valid.expect_invalid(), None);
+		assert_eq!(invalid.expect_invalid(), Some(&Digest(2)));
+	}
+
+	#[test]
+	fn misbehavior_encoding_round_trips() {
+		type TestMisbehavior = Misbehavior<u64, u64, u32, u64>;
+
+		let reports: Vec<(u8, TestMisbehavior)> = vec![
+			(1, Misbehavior::ValidityDoubleVote(ValidityDoubleVote::IssuedAndValidity((1, 2), (3, 4)))),
+			(1, Misbehavior::ValidityDoubleVote(ValidityDoubleVote::IssuedAndInvalidity((1, 2), (3, 4)))),
+			(1, Misbehavior::ValidityDoubleVote(ValidityDoubleVote::ValidityAndInvalidity(1, 2, 3))),
+			(2, Misbehavior::MultipleCandidates(MultipleCandidates {
+				first: (1, 2),
+				second: (3, 4),
+			})),
+			(3, Misbehavior::UnauthorizedStatement(UnauthorizedStatement {
+				statement: SignedStatement {
+					statement: Statement::Valid(5),
+					signature: 6,
+					sender: 7,
+				},
+			})),
+			(4, Misbehavior::DoubleSign(DoubleSign::Candidate(1, 2, 3))),
+			(4, Misbehavior::DoubleSign(DoubleSign::Validity(1, 2, 3))),
+			(4, Misbehavior::DoubleSign(DoubleSign::Invalidity(1, 2, 3))),
+		];
+
+		for (index, report) in reports {
+			let encoded = report.encode();
+			assert_eq!(encoded[0], index);
+			assert_eq!(TestMisbehavior::decode(&mut &encoded[..]).unwrap(), report);
+		}
+	}
 }
@@ -18,6 +18,8 @@ pub mod generic;
 
 pub use generic::Table;
 
+use std::{cmp::Ordering, collections::{HashMap, HashSet}, fmt, hash};
+
 use primitives::parachain::{
 	Id, AbridgedCandidateReceipt, Statement as PrimitiveStatement, ValidatorSignature, ValidatorIndex,
 };
@@ -79,6 +81,167 @@ impl<C: Context> generic::Context for C {
 	}
 }
 
+impl Statement {
+	/// Get the hash of the candidate this statement refers to.
+	pub fn candidate_hash(&self) -> Hash {
+		match *self {
+			generic::Statement::Candidate(ref c) => c.hash(),
+			generic::Statement::Valid(h) | generic::Statement::Invalid(h) => h,
+		}
+	}
+
+	/// Get a compact fingerprint of the statement: its kind along with the candidate hash.
+	///
+	/// This doesn't depend on the full candidate receipt, so it can be used to track
+	/// which statements have been seen without keeping receipts around.
+	pub fn fingerprint(&self) -> PrimitiveStatement {
+		PrimitiveStatement::from(self)
+	}
+
+	// candidate receipts are neither `Hash` nor `Ord`, so statements are hashed and
+	// ordered by the candidate hash, followed by the kind of statement.
+	fn key(&self) -> (Hash, u8) {
+		let kind = match *self {
+			generic::Statement::Candidate(_) => 0,
+			generic::Statement::Valid(_) => 1,
+			generic::Statement::Invalid(_) => 2,
+		};
+
+		(self.candidate_hash(), kind)
+	}
+}
+
+impl hash::Hash for Statement {
+	fn hash<H: hash::Hasher>(&self, state: &mut H) {
+		hash::Hash::hash(&self.key(), state)
+	}
+}
+
+impl PartialOrd for Statement {
+	fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
+impl Ord for Statement {
+	fn cmp(&self, other: &Self) -> Ordering {
+		self.key().cmp(&other.key())
+	}
+}
+
+// Signed statements are ordered by sender, then by statement, with ties broken by
+// the signature, so that distinct signatures on the same statement are kept apart.
+impl PartialOrd for SignedStatement {
+	fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
+impl Ord for SignedStatement {
+	fn cmp(&self, other: &Self) -> Ordering {
+		(self.sender, &self.statement, AsRef::<[u8]>::as_ref(&self.signature))
+			.cmp(&(other.sender, &other.statement, AsRef::<[u8]>::as_ref(&other.signature)))
+	}
+}
+
+/// Whether two signed statements are a double vote, i.e. the same validator
+/// proposing two different candidates.
+///
+/// This is the case when both are `Candidate` statements with the same sender,
+/// whose receipts have the same `relay_parent` but different hashes.
+/// Signatures are not checked.
+pub fn is_double_vote(a: &SignedStatement, b: &SignedStatement) -> bool {
+	if a.sender != b.sender {
+		return false;
+	}
+
+	match (&a.statement, &b.statement) {
+		(generic::Statement::Candidate(a), generic::Statement::Candidate(b)) =>
+			a.relay_parent == b.relay_parent && a.hash() != b.hash(),
+		_ => false,
+	}
+}
+
+/// Whether two signed statements contradict each other, i.e. the same validator
+/// declaring a candidate both valid and invalid.
+///
+/// Signatures are not checked.
+pub fn is_self_contradiction(a: &SignedStatement, b: &SignedStatement) -> bool {
+	if a.sender != b.sender {
+		return false;
+	}
+
+	match (&a.statement, &b.statement) {
+		(generic::Statement::Valid(a), generic::Statement::Invalid(b)) |
+		(generic::Statement::Invalid(a), generic::Statement::Valid(b)) => a == b,
+		_ => false,
+	}
+}
+
+/// Collect the distinct senders of `Valid` statements, grouped by candidate hash.
+///
+/// `Candidate` and `Invalid` statements are ignored, as are duplicate votes.
+/// Signatures are not checked.
+pub fn tally_validity(statements: &[SignedStatement]) -> HashMap<Hash, HashSet<ValidatorIndex>> {
+	let mut tally: HashMap<_, HashSet<_>> = HashMap::new();
+
+	for statement in statements {
+		if let generic::Statement::Valid(hash) = statement.statement {
+			tally.entry(hash).or_default().insert(statement.sender);
+		}
+	}
+
+	tally
+}
+
+impl fmt::Display for Statement {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let kind = match *self {
+			generic::Statement::Candidate(_) => "Candidate",
+			generic::Statement::Valid(_) => "Valid",
+			generic::Statement::Invalid(_) => "Invalid",
+		};
+
+		write!(f, "{}({})", kind, self.candidate_hash())
+	}
+}
+
+impl fmt::Display for Misbehavior {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		use generic::{ValidityDoubleVote, DoubleSign};
+
+		match *self {
+			generic::Misbehavior::ValidityDoubleVote(ref vote) => {
+				let candidate_hash = match *vote {
+					ValidityDoubleVote::IssuedAndValidity((ref c, _), _) |
+					ValidityDoubleVote::IssuedAndInvalidity((ref c, _), _) => c.hash(),
+					ValidityDoubleVote::ValidityAndInvalidity(h, _, _) => h,
+				};
+				write!(f, "ValidityDoubleVote({})", candidate_hash)
+			}
+			generic::Misbehavior::MultipleCandidates(ref m) => write!(
+				f,
+				"MultipleCandidates({}, {})",
+				m.first.0.hash(),
+				m.second.0.hash(),
+			),
+			generic::Misbehavior::UnauthorizedStatement(ref u) => write!(
+				f,
+				"UnauthorizedStatement({} by validator {})",
+				u.statement.statement,
+				u.statement.sender,
+			),
+			generic::Misbehavior::DoubleSign(ref d) => {
+				let candidate_hash = match *d {
+					DoubleSign::Candidate(ref c, _, _) => c.hash(),
+					DoubleSign::Validity(h, _, _) | DoubleSign::Invalidity(h, _, _) => h,
+				};
+				write!(f, "DoubleSign({})", candidate_hash)
+			}
+		}
+	}
+}
+
 impl<'a> From<&'a Statement> for PrimitiveStatement {
 	fn from(s: &'a Statement) -> PrimitiveStatement {
 		match *s {
@@ -88,3 +251,288 @@ impl<'a> From<&'a Statement> for PrimitiveStatement {
 		}
 	}
 }
+
+/// A statement along with the hash of the candidate it refers to.
+///
+/// Hashing a `Candidate` statement means encoding and hashing the whole receipt.
+/// Code which signs or checks the same statement repeatedly can wrap it once, so
+/// that the hash is computed a single time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HashedStatement {
+	statement: Statement,
+	candidate_hash: Hash,
+}
+
+impl HashedStatement {
+	/// Wrap a statement, computing the hash of its candidate.
+	pub fn new(statement: Statement) -> Self {
+		let candidate_hash = statement.candidate_hash();
+		HashedStatement { statement, candidate_hash }
+	}
+
+	/// The wrapped statement.
+	pub fn statement(&self) -> &Statement {
+		&self.statement
+	}
+
+	/// The hash of the candidate the statement refers to.
+	pub fn candidate_hash(&self) -> Hash {
+		self.candidate_hash
+	}
+
+	/// The statement which is actually signed, built from the cached hash.
+	pub fn fingerprint(&self) -> PrimitiveStatement {
+		match self.statement {
+			generic::Statement::Candidate(_) => PrimitiveStatement::Candidate(self.candidate_hash),
+			generic::Statement::Valid(_) => PrimitiveStatement::Valid(self.candidate_hash),
+			generic::Statement::Invalid(_) => PrimitiveStatement::Invalid(self.candidate_hash),
+		}
+	}
+
+	/// Unwrap the statement.
+	pub fn into_inner(self) -> Statement {
+		self.statement
+	}
+}
+
+impl From<Statement> for HashedStatement {
+	fn from(statement: Statement) -> Self {
+		HashedStatement::new(statement)
+	}
+}
+
+impl<'a> From<&'a HashedStatement> for PrimitiveStatement {
+	fn from(s: &'a HashedStatement) -> PrimitiveStatement {
+		s.fingerprint()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn candidate_hash_matches_across_statements() {
+		let candidate = AbridgedCandidateReceipt::default();
+		let hash = candidate.hash();
+
+		let candidate_statement: Statement = generic::Statement::Candidate(candidate);
+		let valid_statement: Statement = generic::Statement::Valid(hash);
+
+		assert_eq!(candidate_statement.candidate_hash(), hash);
+		assert_eq!(valid_statement.candidate_hash(), candidate_statement.candidate_hash());
+	}
+
+	#[test]
+	fn fingerprint_depends_on_kind_and_candidate_hash() {
+		let candidate = AbridgedCandidateReceipt::default();
+		let hash = candidate.hash();
+
+		let seconded: Statement = generic::Statement::Candidate(candidate.clone());
+		let seconded_again: Statement = generic::Statement::Candidate(candidate);
+		let valid: Statement = generic::Statement::Valid(hash);
+
+		assert_eq!(seconded.fingerprint(), seconded_again.fingerprint());
+		assert_eq!(seconded.fingerprint(), PrimitiveStatement::Candidate(hash));
+		assert_ne!(seconded.fingerprint(), valid.fingerprint());
+	}
+
+	#[test]
+	fn hashed_statement_matches_plain_statement() {
+		let candidate = AbridgedCandidateReceipt::default();
+		let hash = candidate.hash();
+
+		let statements: Vec<Statement> = vec![
+			generic::Statement::Candidate(candidate),
+			generic::Statement::Valid(hash),
+			generic::Statement::Invalid(hash),
+		];
+
+		for statement in statements {
+			let hashed = HashedStatement::from(statement.clone());
+			assert_eq!(hashed.candidate_hash(), hash);
+			assert_eq!(PrimitiveStatement::from(&hashed), PrimitiveStatement::from(&statement));
+			assert_eq!(hashed.into_inner(), statement);
+		}
+	}
+
+	#[test]
+	fn statements_deduplicate_in_sets() {
+		use std::collections::{BTreeSet, HashSet};
+
+		let candidate = AbridgedCandidateReceipt::default();
+		let hash = candidate.hash();
+
+		let statements: Vec<Statement> = vec![
+			generic::Statement::Invalid(hash),
+			generic::Statement::Valid(hash),
+			generic::Statement::Candidate(candidate.clone()),
+			generic::Statement::Valid(hash),
+			generic::Statement::Candidate(candidate.clone()),
+		];
+
+		let hash_set: HashSet<_> = statements.iter().cloned().collect();
+		assert_eq!(hash_set.len(), 3);
+
+		let ordered: Vec<_> = statements.into_iter().collect::<BTreeSet<_>>().into_iter().collect();
+		assert_eq!(ordered, vec![
+			generic::Statement::Candidate(candidate),
+			generic::Statement::Valid(hash),
+			generic::Statement::Invalid(hash),
+		]);
+	}
+
+	fn signed(statement: Statement, sender: ValidatorIndex) -> SignedStatement {
+		SignedStatement {
+			statement,
+			signature: Default::default(),
+			sender,
+		}
+	}
+
+	#[test]
+	fn signed_statements_sort_by_sender_then_statement() {
+		let hash_a: Hash = [1; 32].into();
+		let hash_b: Hash = [2; 32].into();
+
+		let mut statements = vec![
+			signed(generic::Statement::Valid(hash_b), 1),
+			signed(generic::Statement::Invalid(hash_a), 0),
+			signed(generic::Statement::Valid(hash_a), 1),
+			signed(generic::Statement::Valid(hash_a), 0),
+		];
+		statements.sort();
+
+		assert_eq!(statements, vec![
+			signed(generic::Statement::Valid(hash_a), 0),
+			signed(generic::Statement::Invalid(hash_a), 0),
+			signed(generic::Statement::Valid(hash_a), 1),
+			signed(generic::Statement::Valid(hash_b), 1),
+		]);
+	}
+
+	#[test]
+	fn ordering_keeps_distinct_signatures_apart() {
+		use std::collections::BTreeSet;
+		use sp_core::sr25519;
+
+		let statement: Statement = generic::Statement::Valid([1; 32].into());
+		let with_signature = |byte| SignedStatement {
+			statement: statement.clone(),
+			signature: sr25519::Signature([byte; 64]).into(),
+			sender: 0,
+		};
+
+		let set: BTreeSet<_> = vec![with_signature(1), with_signature(2), with_signature(1)]
+			.into_iter()
+			.collect();
+
+		assert_eq!(set.len(), 2);
+		assert!(with_signature(1) < with_signature(2));
+	}
+
+	#[test]
+	fn signed_statement_json_roundtrip() {
+		use sp_core::sr25519;
+
+		let candidate = AbridgedCandidateReceipt::default();
+		let hash = candidate.hash();
+		let signature: ValidatorSignature = sr25519::Signature([7; 64]).into();
+
+		let statements: Vec<Statement> = vec![
+			generic::Statement::Candidate(candidate),
+			generic::Statement::Valid(hash),
+			generic::Statement::Invalid(hash),
+		];
+
+		for statement in statements {
+			let signed = SignedStatement { statement, signature: signature.clone(), sender: 3 };
+			let json = serde_json::to_string(&signed).unwrap();
+			assert_eq!(serde_json::from_str::<SignedStatement>(&json).unwrap(), signed);
+		}
+	}
+
+	#[test]
+	fn double_votes_are_detected() {
+		let candidate_a = AbridgedCandidateReceipt::default();
+		let mut candidate_b = AbridgedCandidateReceipt::default();
+		candidate_b.pov_block_hash = [1; 32].into();
+
+		let seconded = |c: &AbridgedCandidateReceipt, sender| {
+			signed(generic::Statement::Candidate(c.clone()), sender)
+		};
+
+		// same candidate.
+		assert!(!is_double_vote(&seconded(&candidate_a, 0), &seconded(&candidate_a, 0)));
+
+		// different candidates from the same sender.
+		assert!(is_double_vote(&seconded(&candidate_a, 0), &seconded(&candidate_b, 0)));
+
+		// different senders.
+		assert!(!is_double_vote(&seconded(&candidate_a, 0), &seconded(&candidate_b, 1)));
+
+		// candidates for different relay parents.
+		candidate_b.relay_parent = [2; 32].into();
+		assert!(!is_double_vote(&seconded(&candidate_a, 0), &seconded(&candidate_b, 0)));
+	}
+
+	#[test]
+	fn self_contradictions_are_detected() {
+		let hash_a: Hash = [1; 32].into();
+		let hash_b: Hash = [2; 32].into();
+
+		let valid = |h, sender| signed(generic::Statement::Valid(h), sender);
+		let invalid = |h, sender| signed(generic::Statement::Invalid(h), sender);
+
+		assert!(is_self_contradiction(&valid(hash_a, 0), &invalid(hash_a, 0)));
+		assert!(is_self_contradiction(&invalid(hash_a, 0), &valid(hash_a, 0)));
+
+		// the same statement repeated.
+		assert!(!is_self_contradiction(&valid(hash_a, 0), &valid(hash_a, 0)));
+		assert!(!is_self_contradiction(&invalid(hash_a, 0), &invalid(hash_a, 0)));
+
+		// different candidates.
+		assert!(!is_self_contradiction(&valid(hash_a, 0), &invalid(hash_b, 0)));
+
+		// different senders.
+		assert!(!is_self_contradiction(&valid(hash_a, 0), &invalid(hash_a, 1)));
+	}
+
+	#[test]
+	fn validity_votes_are_tallied_per_candidate() {
+		let candidate = AbridgedCandidateReceipt::default();
+		let hash_a = candidate.hash();
+		let hash_b: Hash = [2; 32].into();
+
+		let statements = vec![
+			signed(generic::Statement::Candidate(candidate), 0),
+			signed(generic::Statement::Valid(hash_a), 1),
+			signed(generic::Statement::Valid(hash_a), 2),
+			signed(generic::Statement::Valid(hash_a), 2),
+			signed(generic::Statement::Invalid(hash_a), 3),
+			signed(generic::Statement::Valid(hash_b), 3),
+			signed(generic::Statement::Invalid(hash_b), 4),
+		];
+
+		let tally = tally_validity(&statements);
+
+		assert_eq!(tally.len(), 2);
+		assert_eq!(tally[&hash_a], [1, 2].iter().cloned().collect());
+		assert_eq!(tally[&hash_b], [3].iter().cloned().collect());
+	}
+
+	#[test]
+	fn display_uses_abbreviated_candidate_hash() {
+		let hash: Hash = [1; 32].into();
+		let abbreviated = format!("{}", hash);
+		assert!(abbreviated.len() < 66);
+
+		let statement: Statement = generic::Statement::Valid(hash);
+		assert_eq!(format!("{}", statement), format!("Valid({})", abbreviated));
+
+		let misbehavior: Misbehavior = generic::Misbehavior::DoubleSign(
+			generic::DoubleSign::Invalidity(hash, Default::default(), Default::default()),
+		);
+		assert_eq!(format!("{}", misbehavior), format!("DoubleSign({})", abbreviated));
+	}
+}
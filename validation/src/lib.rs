@@ -33,13 +33,13 @@ use std::{
 	collections::{HashMap, HashSet},
 	sync::Arc,
 };
-use codec::Encode;
 use polkadot_primitives::parachain::{
 	Id as ParaId, Chain, DutyRoster, AbridgedCandidateReceipt,
 	Statement as PrimitiveStatement,
 	PoVBlock, ErasureChunk, ValidatorSignature, ValidatorIndex,
-	ValidatorPair, ValidatorId, SigningContext,
+	ValidatorPair, ValidatorId, SigningContext, SessionIndex, localized_payload,
 };
+use polkadot_primitives::Hash;
 use primitives::Pair;
 
 use futures::prelude::*;
@@ -49,7 +49,7 @@ pub use self::collation::Collators;
 pub use self::error::Error;
 pub use self::shared_table::{
 	SharedTable, ParachainWork, PrimedParachainWork, Validated, Statement, SignedStatement,
-	GenericStatement,
+	GenericStatement, HashedStatement,
 };
 pub use self::validation_service::{ServiceHandle, ServiceBuilder};
 
@@ -131,6 +131,17 @@ pub struct GroupInfo {
 	needed_validity: usize,
 }
 
+// The signing context used by substrate's sr25519 implementation, needed to
+// build the transcripts for batch verification. This must match the context
+// `sp_core::sr25519` signs and verifies with; see
+// `batch_signing_context_matches_sr25519`.
+const SR25519_SIGNING_CTX: &[u8] = b"substrate";
+
+// The message signed for a table statement, given its compact form.
+fn statement_payload(statement: PrimitiveStatement, signing_context: &SigningContext) -> Vec<u8> {
+	localized_payload(statement, signing_context)
+}
+
 /// Sign a table statement against a parent hash.
 /// The actual message signed is the encoded statement concatenated with the
 /// parent hash.
@@ -139,10 +150,30 @@ pub fn sign_table_statement(
 	key: &ValidatorPair,
 	signing_context: &SigningContext,
 ) -> ValidatorSignature {
-	let mut encoded = PrimitiveStatement::from(statement).encode();
-	encoded.extend(signing_context.encode());
+	key.sign(&statement_payload(statement.into(), signing_context))
+}
+
+/// Sign a table statement like `sign_table_statement`, using the candidate hash
+/// cached in the `HashedStatement` instead of hashing the receipt again.
+pub fn sign_hashed_statement(
+	statement: &HashedStatement,
+	key: &ValidatorPair,
+	signing_context: &SigningContext,
+) -> ValidatorSignature {
+	key.sign(&statement_payload(statement.fingerprint(), signing_context))
+}
+
+/// Sign a table statement as the validator at index `sender`, producing
+/// a `SignedStatement` which passes `check_signed_statement`.
+pub fn sign_statement(
+	statement: Statement,
+	key: &ValidatorPair,
+	sender: ValidatorIndex,
+	signing_context: &SigningContext,
+) -> SignedStatement {
+	let signature = sign_table_statement(&statement, key, signing_context);
 
-	key.sign(&encoded)
+	SignedStatement { statement, signature, sender }
 }
 
 /// Check signature on table statement.
@@ -151,13 +182,203 @@ pub fn check_statement(
 	signature: &ValidatorSignature,
 	signer: ValidatorId,
 	signing_context: &SigningContext,
+) -> bool {
+	check_fingerprint(statement.into(), signature, signer, signing_context)
+}
+
+/// Check the signature on a table statement like `check_statement`, using the
+/// candidate hash cached in the `HashedStatement` instead of hashing the receipt again.
+pub fn check_hashed_statement(
+	statement: &HashedStatement,
+	signature: &ValidatorSignature,
+	signer: ValidatorId,
+	signing_context: &SigningContext,
+) -> bool {
+	check_fingerprint(statement.fingerprint(), signature, signer, signing_context)
+}
+
+// Check a signature on the compact form of a statement.
+fn check_fingerprint(
+	fingerprint: PrimitiveStatement,
+	signature: &ValidatorSignature,
+	signer: ValidatorId,
+	signing_context: &SigningContext,
 ) -> bool {
 	use runtime_primitives::traits::AppVerify;
 
-	let mut encoded = PrimitiveStatement::from(statement).encode();
-	encoded.extend(signing_context.encode());
+	signature.verify(&statement_payload(fingerprint, signing_context)[..], &signer)
+}
+
+/// Errors that can occur when checking the signature of a `SignedStatement`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CheckSignatureError {
+	/// The sender index doesn't refer to any validator in the set.
+	ValidatorIndexOutOfBounds {
+		/// The sender index given in the statement.
+		index: ValidatorIndex,
+		/// The number of validators in the set.
+		len: usize,
+	},
+	/// The signature doesn't verify against the sender's key.
+	BadSignature,
+	/// The signing context is for a different session than expected.
+	SessionMismatch {
+		/// The session the statement was expected to be signed in.
+		expected: SessionIndex,
+		/// The session of the signing context that was provided.
+		got: SessionIndex,
+	},
+	/// The sender is not a member of the group the statement was checked against.
+	NotInGroup(ValidatorIndex),
+}
+
+/// Check the signature on a signed table statement, looking up the sender
+/// within the given validator set.
+pub fn check_signed_statement(
+	statement: &SignedStatement,
+	validators: &[ValidatorId],
+	signing_context: &SigningContext,
+) -> Result<(), CheckSignatureError> {
+	let signer = validators.get(statement.sender as usize)
+		.ok_or(CheckSignatureError::ValidatorIndexOutOfBounds {
+			index: statement.sender,
+			len: validators.len(),
+		})?;
+
+	if check_statement(&statement.statement, &statement.signature, signer.clone(), signing_context) {
+		Ok(())
+	} else {
+		Err(CheckSignatureError::BadSignature)
+	}
+}
+
+/// Check the signature on a signed table statement like `check_signed_statement`,
+/// but first ensure that the signing context belongs to the expected session.
+///
+/// This guards against accepting statements replayed from another session.
+pub fn check_signed_statement_in_session(
+	statement: &SignedStatement,
+	validators: &[ValidatorId],
+	signing_context: &SigningContext,
+	session_index: SessionIndex,
+) -> Result<(), CheckSignatureError> {
+	if signing_context.session_index != session_index {
+		return Err(CheckSignatureError::SessionMismatch {
+			expected: session_index,
+			got: signing_context.session_index,
+		});
+	}
+
+	check_signed_statement(statement, validators, signing_context)
+}
+
+/// Check the signature on a signed table statement, made on top of `relay_parent`
+/// in session `session_index`.
+///
+/// `Valid` and `Invalid` statements only carry a candidate hash, so it is the
+/// signing context that binds them to a relay parent. This builds that context
+/// rather than relying on the caller to provide the right one.
+pub fn check_signed_statement_for(
+	statement: &SignedStatement,
+	relay_parent: Hash,
+	session_index: SessionIndex,
+	validators: &[ValidatorId],
+) -> Result<(), CheckSignatureError> {
+	let signing_context = SigningContext {
+		session_index,
+		parent_hash: relay_parent,
+	};
+
+	check_signed_statement(statement, validators, &signing_context)
+}
+
+/// Check the signature on a signed table statement like `check_signed_statement`,
+/// additionally requiring the sender to be a member of `group`.
+///
+/// `group` holds the indices into `validators` of the validators assigned to the
+/// candidate's parachain. Statements by anyone else are not valid backing votes,
+/// however well signed.
+pub fn check_signed_statement_in_group(
+	statement: &SignedStatement,
+	group: &[ValidatorIndex],
+	validators: &[ValidatorId],
+	signing_context: &SigningContext,
+) -> Result<(), CheckSignatureError> {
+	if !group.contains(&statement.sender) {
+		return Err(CheckSignatureError::NotInGroup(statement.sender));
+	}
+
+	check_signed_statement(statement, validators, signing_context)
+}
+
+/// Check the signatures of many signed table statements at once.
+///
+/// This uses sr25519 batch verification, falling back to checking statements
+/// individually if the batch fails. Signatures the batch verifier can't parse are
+/// always checked individually, so the result agrees with `check_signed_statement`
+/// for every statement. On failure, the indices of all statements that didn't
+/// pass are returned in ascending order. Statements whose sender isn't in the
+/// validator set are reported as failed.
+pub fn check_signatures_batch(
+	statements: &[SignedStatement],
+	validators: &[ValidatorId],
+	signing_context: &SigningContext,
+) -> Result<(), Vec<usize>> {
+	// hash each candidate receipt once, for both the batch and any individual checks.
+	let fingerprints: Vec<_> = statements.iter().map(|s| s.statement.fingerprint()).collect();
+
+	let mut failed = Vec::new();
+	let mut unbatched = Vec::new();
+	let mut batched = Vec::with_capacity(statements.len());
+	let mut transcripts = Vec::with_capacity(statements.len());
+	let mut signatures = Vec::with_capacity(statements.len());
+	let mut public_keys = Vec::with_capacity(statements.len());
+
+	for (i, statement) in statements.iter().enumerate() {
+		let signer = match validators.get(statement.sender as usize) {
+			Some(signer) => signer,
+			None => {
+				failed.push(i);
+				continue;
+			}
+		};
+
+		let public = schnorrkel::PublicKey::from_bytes(signer.as_ref());
+		let signature = schnorrkel::Signature::from_bytes(statement.signature.as_ref());
+
+		match (public, signature) {
+			(Ok(public), Ok(signature)) => {
+				let payload = statement_payload(fingerprints[i].clone(), signing_context);
+				transcripts.push(schnorrkel::signing_context(SR25519_SIGNING_CTX).bytes(&payload[..]));
+				signatures.push(signature);
+				public_keys.push(public);
+				batched.push(i);
+			}
+			// encodings schnorrkel rejects may still be accepted by the
+			// individual check, so leave the verdict to it.
+			_ => unbatched.push(i),
+		}
+	}
+
+	let batch_ok = batched.is_empty() ||
+		schnorrkel::verify_batch(transcripts, &signatures[..], &public_keys[..], false).is_ok();
+
+	if !batch_ok {
+		// the batch only tells us that some signature is bad, so find out which.
+		unbatched.extend(batched);
+	}
 
-	signature.verify(&encoded[..], &signer)
+	failed.extend(unbatched.into_iter().filter(|&i| {
+		let signer = validators[statements[i].sender as usize].clone();
+		!check_fingerprint(fingerprints[i].clone(), &statements[i].signature, signer, signing_context)
+	}));
+	failed.sort();
+
+	if failed.is_empty() {
+		Ok(())
+	} else {
+		Err(failed)
+	}
 }
 
 /// Compute group info out of a duty roster and a local authority set.
@@ -232,4 +453,285 @@ mod tests {
 		assert!(!check_statement(&statement, &sig, Sr25519Keyring::Alice.public().into(), &wrong_signing_context));
 		assert!(!check_statement(&statement, &sig, Sr25519Keyring::Bob.public().into(), &signing_context));
 	}
+
+	#[test]
+	fn check_signed_statement_reports_errors() {
+		let statement: Statement = GenericStatement::Valid([1; 32].into());
+		let signing_context = SigningContext {
+			session_index: Default::default(),
+			parent_hash: [2; 32].into(),
+		};
+		let validators: Vec<ValidatorId> = vec![
+			Sr25519Keyring::Alice.public().into(),
+			Sr25519Keyring::Bob.public().into(),
+		];
+
+		let signature = sign_table_statement(&statement, &Sr25519Keyring::Alice.pair().into(), &signing_context);
+
+		let good = SignedStatement { statement: statement.clone(), signature: signature.clone(), sender: 0 };
+		assert_eq!(check_signed_statement(&good, &validators, &signing_context), Ok(()));
+
+		let wrong_sender = SignedStatement { statement: statement.clone(), signature: signature.clone(), sender: 1 };
+		assert_eq!(
+			check_signed_statement(&wrong_sender, &validators, &signing_context),
+			Err(CheckSignatureError::BadSignature),
+		);
+
+		let out_of_bounds = SignedStatement { statement, signature, sender: 5 };
+		assert_eq!(
+			check_signed_statement(&out_of_bounds, &validators, &signing_context),
+			Err(CheckSignatureError::ValidatorIndexOutOfBounds { index: 5, len: 2 }),
+		);
+	}
+
+	#[test]
+	fn signed_statement_passes_check() {
+		let signing_context = SigningContext {
+			session_index: Default::default(),
+			parent_hash: [2; 32].into(),
+		};
+		let validators: Vec<ValidatorId> = vec![
+			Sr25519Keyring::Alice.public().into(),
+			Sr25519Keyring::Bob.public().into(),
+		];
+
+		let signed = sign_statement(
+			GenericStatement::Invalid([1; 32].into()),
+			&Sr25519Keyring::Bob.pair().into(),
+			1,
+			&signing_context,
+		);
+
+		assert_eq!(signed.sender, 1);
+		assert_eq!(check_signed_statement(&signed, &validators, &signing_context), Ok(()));
+	}
+
+	#[test]
+	fn statement_from_other_session_is_rejected() {
+		let session_context = |session_index| SigningContext {
+			session_index,
+			parent_hash: [2; 32].into(),
+		};
+		let validators: Vec<ValidatorId> = vec![Sr25519Keyring::Alice.public().into()];
+
+		let signed = sign_statement(
+			GenericStatement::Valid([1; 32].into()),
+			&Sr25519Keyring::Alice.pair().into(),
+			0,
+			&session_context(5),
+		);
+
+		assert_eq!(
+			check_signed_statement_in_session(&signed, &validators, &session_context(5), 5),
+			Ok(()),
+		);
+		assert_eq!(
+			check_signed_statement_in_session(&signed, &validators, &session_context(6), 5),
+			Err(CheckSignatureError::SessionMismatch { expected: 5, got: 6 }),
+		);
+
+		// the session matches, but the signature was made in another one.
+		assert_eq!(
+			check_signed_statement_in_session(&signed, &validators, &session_context(6), 6),
+			Err(CheckSignatureError::BadSignature),
+		);
+	}
+
+	#[test]
+	fn statement_from_outside_group_is_rejected() {
+		let signing_context = SigningContext {
+			session_index: Default::default(),
+			parent_hash: [2; 32].into(),
+		};
+		let validators: Vec<ValidatorId> = vec![
+			Sr25519Keyring::Alice.public().into(),
+			Sr25519Keyring::Bob.public().into(),
+			Sr25519Keyring::Charlie.public().into(),
+		];
+		let group = [0, 2];
+
+		let signed = |key: Sr25519Keyring, sender| sign_statement(
+			GenericStatement::Valid([1; 32].into()),
+			&key.pair().into(),
+			sender,
+			&signing_context,
+		);
+
+		assert_eq!(
+			check_signed_statement_in_group(&signed(Sr25519Keyring::Charlie, 2), &group, &validators, &signing_context),
+			Ok(()),
+		);
+		assert_eq!(
+			check_signed_statement_in_group(&signed(Sr25519Keyring::Bob, 2), &group, &validators, &signing_context),
+			Err(CheckSignatureError::BadSignature),
+		);
+		assert_eq!(
+			check_signed_statement_in_group(&signed(Sr25519Keyring::Bob, 1), &group, &validators, &signing_context),
+			Err(CheckSignatureError::NotInGroup(1)),
+		);
+	}
+
+	#[test]
+	fn statement_for_other_relay_parent_is_rejected() {
+		let relay_parent: Hash = [2; 32].into();
+		let signing_context = SigningContext {
+			session_index: 3,
+			parent_hash: relay_parent,
+		};
+		let validators: Vec<ValidatorId> = vec![Sr25519Keyring::Alice.public().into()];
+
+		let signed = sign_statement(
+			GenericStatement::Valid([1; 32].into()),
+			&Sr25519Keyring::Alice.pair().into(),
+			0,
+			&signing_context,
+		);
+
+		assert_eq!(check_signed_statement_for(&signed, relay_parent, 3, &validators), Ok(()));
+		assert_eq!(
+			check_signed_statement_for(&signed, [0xff; 32].into(), 3, &validators),
+			Err(CheckSignatureError::BadSignature),
+		);
+		assert_eq!(
+			check_signed_statement_for(&signed, relay_parent, 4, &validators),
+			Err(CheckSignatureError::BadSignature),
+		);
+	}
+
+	#[test]
+	fn hashed_statements_sign_and_check_like_plain_ones() {
+		let signing_context = SigningContext {
+			session_index: Default::default(),
+			parent_hash: [2; 32].into(),
+		};
+		let key: ValidatorPair = Sr25519Keyring::Alice.pair().into();
+		let signer: ValidatorId = Sr25519Keyring::Alice.public().into();
+		let candidate = AbridgedCandidateReceipt::default();
+		let hash = candidate.hash();
+
+		let statements: Vec<Statement> = vec![
+			GenericStatement::Candidate(candidate),
+			GenericStatement::Valid(hash),
+			GenericStatement::Invalid(hash),
+		];
+
+		for statement in statements {
+			let hashed = HashedStatement::new(statement.clone());
+
+			let signature = sign_hashed_statement(&hashed, &key, &signing_context);
+			assert!(check_statement(&statement, &signature, signer.clone(), &signing_context));
+
+			let signature = sign_table_statement(&statement, &key, &signing_context);
+			assert!(check_hashed_statement(&hashed, &signature, signer.clone(), &signing_context));
+		}
+	}
+
+	#[test]
+	fn check_signatures_batch_reports_failed_indices() {
+		let signing_context = SigningContext {
+			session_index: Default::default(),
+			parent_hash: [2; 32].into(),
+		};
+		let validators: Vec<ValidatorId> = vec![
+			Sr25519Keyring::Alice.public().into(),
+			Sr25519Keyring::Bob.public().into(),
+		];
+
+		let signed = |statement: Statement, key: Sr25519Keyring, sender| {
+			let signature = sign_table_statement(&statement, &key.pair().into(), &signing_context);
+			SignedStatement { statement, signature, sender }
+		};
+
+		let valid = vec![
+			signed(GenericStatement::Valid([1; 32].into()), Sr25519Keyring::Alice, 0),
+			signed(GenericStatement::Invalid([3; 32].into()), Sr25519Keyring::Bob, 1),
+		];
+
+		assert_eq!(check_signatures_batch(&[], &validators, &signing_context), Ok(()));
+		assert_eq!(check_signatures_batch(&valid, &validators, &signing_context), Ok(()));
+
+		let mut mixed = valid.clone();
+		// signed by Alice, but claims to be from Bob.
+		mixed.push(signed(GenericStatement::Valid([4; 32].into()), Sr25519Keyring::Alice, 1));
+		// no such validator.
+		mixed.push(signed(GenericStatement::Valid([5; 32].into()), Sr25519Keyring::Alice, 7));
+
+		assert_eq!(check_signatures_batch(&mixed, &validators, &signing_context), Err(vec![2, 3]));
+	}
+
+	#[test]
+	fn batch_and_single_checks_agree() {
+		let signing_context = SigningContext {
+			session_index: Default::default(),
+			parent_hash: [2; 32].into(),
+		};
+		let validators: Vec<ValidatorId> = vec![
+			Sr25519Keyring::Alice.public().into(),
+			Sr25519Keyring::Bob.public().into(),
+		];
+
+		let signed = |statement: Statement, key: Sr25519Keyring, sender| {
+			let signature = sign_table_statement(&statement, &key.pair().into(), &signing_context);
+			SignedStatement { statement, signature, sender }
+		};
+
+		let valid = signed(GenericStatement::Valid([1; 32].into()), Sr25519Keyring::Alice, 0);
+
+		// the same signature without the schnorrkel marker bit, which the batch
+		// verifier can't parse.
+		let mut unmarked = valid.clone();
+		let mut bytes = [0u8; 64];
+		bytes.copy_from_slice(unmarked.signature.as_ref());
+		bytes[63] &= 0x7f;
+		unmarked.signature = primitives::sr25519::Signature(bytes).into();
+
+		let mut unparseable = valid.clone();
+		unparseable.signature = primitives::sr25519::Signature([0; 64]).into();
+
+		let statements = vec![
+			valid,
+			signed(GenericStatement::Invalid([3; 32].into()), Sr25519Keyring::Bob, 1),
+			signed(GenericStatement::Valid([4; 32].into()), Sr25519Keyring::Alice, 1),
+			unmarked,
+			unparseable,
+		];
+
+		let single_failures: Vec<_> = statements.iter()
+			.enumerate()
+			.filter(|(_, s)| check_signed_statement(s, &validators, &signing_context).is_err())
+			.map(|(i, _)| i)
+			.collect();
+
+		for (i, statement) in statements.iter().enumerate() {
+			assert_eq!(
+				check_signatures_batch(&[statement.clone()], &validators, &signing_context).is_ok(),
+				!single_failures.contains(&i),
+			);
+		}
+
+		assert_eq!(
+			check_signatures_batch(&statements, &validators, &signing_context),
+			Err(single_failures),
+		);
+	}
+
+	#[test]
+	fn batch_signing_context_matches_sr25519() {
+		let signing_context = SigningContext {
+			session_index: Default::default(),
+			parent_hash: [2; 32].into(),
+		};
+		let key = Sr25519Keyring::Alice;
+		let statement = GenericStatement::Valid([1; 32].into());
+		let signature = sign_table_statement(&statement, &key.pair().into(), &signing_context);
+
+		// the batch path must accept a signature on its own, without falling back
+		// to `check_statement`.
+		let public = schnorrkel::PublicKey::from_bytes(key.public().as_ref()).unwrap();
+		let signature = schnorrkel::Signature::from_bytes(signature.as_ref()).unwrap();
+		let payload = statement_payload((&statement).into(), &signing_context);
+		let transcript = schnorrkel::signing_context(SR25519_SIGNING_CTX).bytes(&payload[..]);
+
+		assert!(public.verify(transcript, &signature).is_ok());
+	}
 }
@@ -187,26 +187,11 @@ impl<'a> ValidatedCandidate<'a> {
 			omitted_validation,
 		};
 
-		let erasure_chunks = erasure::obtain_chunks(
+		let (chunks, erasure_root) = erasure::available_data_to_chunks(
 			n_validators,
 			&available_data,
 		)?;
 
-		let branches = erasure::branches(erasure_chunks.as_ref());
-		let erasure_root = branches.root();
-
-		let chunks: Vec<_> = erasure_chunks
-			.iter()
-			.zip(branches.map(|(proof, _)| proof))
-			.enumerate()
-			.map(|(index, (chunk, proof))| ErasureChunk {
-				// branches borrows the original chunks, but this clone could probably be dodged.
-				chunk: chunk.clone(),
-				index: index as u32,
-				proof,
-			})
-			.collect();
-
 		let commitments = CandidateCommitments {
 			upward_messages,
 			fees,
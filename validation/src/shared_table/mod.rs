@@ -44,7 +44,7 @@ use crate::Error;
 
 mod includable;
 
-pub use table::{SignedStatement, Statement};
+pub use table::{SignedStatement, Statement, HashedStatement};
 pub use table::generic::Statement as GenericStatement;
 
 struct TableContext {
@@ -84,16 +84,17 @@ impl TableContext {
 		)
 	}
 
-	fn sign_statement(&self, statement: table::Statement) -> Option<table::SignedStatement> {
+	fn sign_statement(&self, statement: HashedStatement) -> Option<table::SignedStatement> {
 		self.local_index().and_then(move |sender|
-			self.key.as_ref()
-				.map(|key| crate::sign_table_statement(
-						&statement,
-						key,
-						&self.signing_context,
-					).into()
-				)
-				.map(move |signature| table::SignedStatement { statement, signature, sender })
+			self.key.as_ref().map(|key| {
+				let signature = crate::sign_hashed_statement(&statement, key, &self.signing_context);
+
+				table::SignedStatement {
+					statement: statement.into_inner(),
+					signature,
+					sender,
+				}
+			})
 		)
 	}
 }
@@ -510,12 +511,10 @@ impl SharedTable {
 	pub fn import_validated(&self, validated: Validated)
 		-> Option<SignedStatement>
 	{
-		let digest = match validated.statement {
-			GenericStatement::Candidate(ref c) => c.hash(),
-			GenericStatement::Valid(h) | GenericStatement::Invalid(h) => h,
-		};
+		let statement = HashedStatement::new(validated.statement);
+		let digest = statement.candidate_hash();
 
-		let signed_statement = self.context.sign_statement(validated.statement);
+		let signed_statement = self.context.sign_statement(statement);
 
 		if let Some(ref signed) = signed_statement {
 			let mut inner = self.inner.lock();
@@ -785,10 +784,22 @@ mod tests {
 		let local_index = 0;
 		let n_validators = 2;
 
+		let available_data = AvailableData {
+			pov_block: pov_block.clone(),
+			omitted_validation: Default::default(),
+		};
+		let (chunks, erasure_root) = erasure::available_data_to_chunks(
+			n_validators,
+			&available_data,
+		).unwrap();
+
 		let mut candidate = AbridgedCandidateReceipt::default();
 		candidate.parachain_index = para_id;
 		candidate.relay_parent = relay_parent;
 		candidate.pov_block_hash = pov_block_hash;
+		candidate.commitments.erasure_root = erasure_root;
+
+		let commitments = candidate.commitments.clone();
 
 		let candidate_hash = candidate.hash();
 
@@ -816,16 +827,9 @@ mod tests {
 
 		let validated = block_on(producer.prime_with(|_, _| Ok(
 			FullOutput {
-				available_data: AvailableData {
-					pov_block: pov_block.clone(),
-					omitted_validation: Default::default(),
-				},
-				erasure_chunks: (0..n_validators).map(|i| ErasureChunk {
-					chunk: vec![1, 2, 3],
-					index: i as u32,
-					proof: vec![],
-				}).collect(),
-				commitments: Default::default(),
+				available_data: available_data.clone(),
+				erasure_chunks: chunks.clone(),
+				commitments: commitments.clone(),
 				n_validators,
 			}
 		)).validate()).unwrap();
@@ -848,19 +852,24 @@ mod tests {
 		let local_index = 0;
 		let n_validators = 2;
 
+		let available_data = AvailableData {
+			pov_block: pov_block.clone(),
+			omitted_validation: Default::default(),
+		};
+
+		let (chunks, erasure_root) = erasure::available_data_to_chunks(
+			n_validators,
+			&available_data,
+		).unwrap();
+
 		let mut candidate = AbridgedCandidateReceipt::default();
 		candidate.parachain_index = para_id;
 		candidate.pov_block_hash = pov_block_hash;
 		candidate.relay_parent = relay_parent;
+		candidate.commitments.erasure_root = erasure_root;
 
 		let candidate_hash = candidate.hash();
-
-		let available_data = AvailableData {
-			pov_block: pov_block.clone(),
-			omitted_validation: Default::default(),
-		};
-
-		let chunks = erasure::obtain_chunks(n_validators, &available_data).unwrap();
+		let commitments = candidate.commitments.clone();
 
 		store.note_validator_index_and_n_validators(
 			&relay_parent,
@@ -882,16 +891,9 @@ mod tests {
 
 		let validated = block_on(producer.prime_with(|_, _| Ok(
 			FullOutput {
-				available_data: AvailableData {
-					pov_block: pov_block.clone(),
-					omitted_validation: Default::default(),
-				},
-				erasure_chunks: (0..n_validators).map(|i| ErasureChunk {
-					chunk: chunks[i].clone(),
-					index: i as u32,
-					proof: vec![],
-				}).collect(),
-				commitments: Default::default(),
+				available_data: available_data.clone(),
+				erasure_chunks: chunks.clone(),
+				commitments: commitments.clone(),
 				n_validators,
 			}
 		)).validate()).unwrap();